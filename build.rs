@@ -0,0 +1,179 @@
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use toml::{Table, Value};
+
+// Generates the compiled-in `Default` parameters from params/bundle.toml so the
+// Rust sources never carry hand-synced literals.
+const BUNDLE_PATH: &str = "params/bundle.toml";
+
+fn main() {
+    println!("cargo:rerun-if-changed={BUNDLE_PATH}");
+
+    #[cfg(feature = "capi")]
+    generate_header();
+
+    #[cfg(feature = "node")]
+    napi_build::setup();
+
+    let content = fs::read_to_string(BUNDLE_PATH)
+        .unwrap_or_else(|e| panic!("failed to read {BUNDLE_PATH}: {e}"));
+    let bundle: Table = content
+        .parse()
+        .unwrap_or_else(|e| panic!("failed to parse {BUNDLE_PATH}: {e}"));
+
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let out_dir = Path::new(&out_dir).join("params");
+    fs::create_dir_all(&out_dir).unwrap();
+
+    let write = |name: &str, code: String| {
+        fs::write(out_dir.join(name), code).unwrap();
+    };
+
+    write(
+        "simple.rs",
+        struct_literal("SimpleParameters", section(&bundle, &["simple"])),
+    );
+    write(
+        "basic.rs",
+        struct_literal("BasicParameters", section(&bundle, &["basic"])),
+    );
+    write(
+        "chat.rs",
+        struct_literal("ChatParameters", section(&bundle, &["chat"])),
+    );
+    write(
+        "tools.rs",
+        struct_literal("ToolParameters", section(&bundle, &["tools"])),
+    );
+    write(
+        "json.rs",
+        struct_literal("JsonParameters", section(&bundle, &["json"])),
+    );
+    write(
+        "code.rs",
+        struct_literal("CodeParameters", section(&bundle, &["code"])),
+    );
+    write(
+        "multilingual_default.rs",
+        struct_literal(
+            "MultilingualParameters",
+            section(&bundle, &["multilingual", "default_params"]),
+        ),
+    );
+    write(
+        "multilingual.rs",
+        language_map_literal(
+            "MultilingualMethodParameters",
+            "MultilingualParameters",
+            section(&bundle, &["multilingual"]),
+        ),
+    );
+    write(
+        "multilingual_simple_default.rs",
+        struct_literal(
+            "MultilingualSimpleParameters",
+            section(&bundle, &["multilingual_simple", "default_params"]),
+        ),
+    );
+    write(
+        "multilingual_simple.rs",
+        language_map_literal(
+            "MultilingualSimpleMethodParameters",
+            "MultilingualSimpleParameters",
+            section(&bundle, &["multilingual_simple"]),
+        ),
+    );
+}
+
+// The header is generated into OUT_DIR only. The copy in include/ is checked
+// in, so C and Go callers can build against it without Rust tooling; the capi
+// tests check it is current and SKIMTOKEN_UPDATE_HEADER=1 refreshes it.
+#[cfg(feature = "capi")]
+fn generate_header() {
+    const CAPI_PATH: &str = "src/capi.rs";
+    println!("cargo:rerun-if-changed={CAPI_PATH}");
+
+    let config = cbindgen::Config {
+        language: cbindgen::Language::C,
+        include_guard: Some("SKIMTOKEN_H".to_string()),
+        header: Some("/* Generated from src/capi.rs by build.rs, do not edit */".to_string()),
+        cpp_compat: true,
+        usize_is_size_t: true,
+        // cbindgen skips `usize::MAX`, so SKIMTOKEN_ERROR is spelled out here
+        after_includes: Some(
+            "\n/* Returned by skimtoken_estimate on failure */\n#define SKIMTOKEN_ERROR SIZE_MAX"
+                .to_string(),
+        ),
+        documentation_style: cbindgen::DocumentationStyle::C99,
+        ..cbindgen::Config::default()
+    };
+    cbindgen::Builder::new()
+        .with_src(CAPI_PATH)
+        .with_config(config)
+        .generate()
+        .unwrap_or_else(|e| panic!("failed to generate the C header: {e}"))
+        .write_to_file(Path::new(&env::var("OUT_DIR").unwrap()).join("skimtoken.h"));
+}
+
+fn section<'a>(table: &'a Table, path: &[&str]) -> &'a Table {
+    let mut current = table;
+    for key in path {
+        current = current
+            .get(*key)
+            .and_then(Value::as_table)
+            .unwrap_or_else(|| panic!("missing [{}] in {BUNDLE_PATH}", path.join(".")));
+    }
+    current
+}
+
+fn struct_literal(type_name: &str, table: &Table) -> String {
+    let mut code = format!("{type_name} {{\n");
+    for (key, value) in table {
+        let value = match value {
+            Value::Float(v) => *v,
+            Value::Integer(v) => *v as f64,
+            _ => panic!("{type_name}.{key} must be a number in {BUNDLE_PATH}"),
+        };
+        // Debug formatting of an f32 is the shortest literal that round-trips
+        writeln!(code, "    {key}: {:?},", value as f32).unwrap();
+    }
+    code.push('}');
+    code
+}
+
+fn language_map_literal(type_name: &str, params_type: &str, method: &Table) -> String {
+    let mut code = String::from("{\n    let mut language_params = HashMap::new();\n");
+    for (lang, params) in section(method, &["language_params"]) {
+        let params = params
+            .as_table()
+            .unwrap_or_else(|| panic!("language_params.{lang} must be a table"));
+        writeln!(
+            code,
+            "    language_params.insert(\"{lang}\".to_string(), {});",
+            struct_literal(params_type, params)
+        )
+        .unwrap();
+    }
+
+    // Sample counts are optional; hand-written entries may not have them
+    code.push_str("    let language_samples = HashMap::from([\n");
+    if let Some(samples) = method.get("language_samples").and_then(Value::as_table) {
+        for (lang, count) in samples {
+            let count = count
+                .as_integer()
+                .unwrap_or_else(|| panic!("language_samples.{lang} must be an integer"));
+            writeln!(code, "        (\"{lang}\".to_string(), {count}usize),").unwrap();
+        }
+    }
+    code.push_str("    ]);\n");
+
+    writeln!(
+        code,
+        "    {type_name} {{\n        default_params: {params_type}::default(),\n        language_params,\n        language_samples,\n    }}\n}}"
+    )
+    .unwrap();
+    code
+}
@@ -0,0 +1,56 @@
+/* Generated from src/capi.rs by build.rs, do not edit */
+
+#ifndef SKIMTOKEN_H
+#define SKIMTOKEN_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/* Returned by skimtoken_estimate on failure */
+#define SKIMTOKEN_ERROR SIZE_MAX
+
+
+
+// Estimator for one method and parameter set; shareable across threads
+typedef struct SkimtokenEstimator SkimtokenEstimator;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Create an estimator for `method` (e.g. "multilingual_simple"), with
+// parameters from the TOML bundle in `params_toml`. Either may be NULL for
+// the default method and the compiled-in parameters. Returns NULL on error,
+// see `skimtoken_last_error`.
+//
+// # Safety
+// `method` and `params_toml` are NULL or NUL-terminated strings.
+struct SkimtokenEstimator *skimtoken_estimator_new(const char *method, const char *params_toml);
+
+// Estimate the tokens of `len` bytes of UTF-8 text at `ptr`; invalid UTF-8
+// is replaced with U+FFFD. Returns `SKIMTOKEN_ERROR` on error, see
+// `skimtoken_last_error`.
+//
+// # Safety
+// `handle` comes from `skimtoken_estimator_new` and was not freed; `ptr`
+// points to `len` readable bytes, or `len` is 0.
+size_t skimtoken_estimate(const struct SkimtokenEstimator *handle, const uint8_t *ptr, size_t len);
+
+// Free an estimator; NULL is ignored.
+//
+// # Safety
+// `handle` comes from `skimtoken_estimator_new` and is not used afterwards.
+void skimtoken_estimator_free(struct SkimtokenEstimator *handle);
+
+// Message of the last error on this thread, or NULL. The string stays valid
+// until the next skimtoken call on the same thread.
+const char *skimtoken_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* SKIMTOKEN_H */
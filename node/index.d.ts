@@ -0,0 +1,70 @@
+/** Type definitions for skimtoken, mirroring skimtoken/*.pyi */
+
+export type Method =
+  | "simple"
+  | "basic"
+  | "multilingual"
+  | "multilingual_simple"
+  | "json"
+  | "code";
+
+/** Estimate token count for text using specified method (default multilingual_simple). */
+export function estimateTokens(text: string, method?: Method | null): number;
+
+/** Estimate token count using simple character count method. */
+export function estimateTokensSimple(text: string): number;
+
+/** Estimate token count using basic features (chars, words, etc.). */
+export function estimateTokensBasic(text: string): number;
+
+/** Estimate token count using language-specific parameters. */
+export function estimateTokensMultilingual(text: string): number;
+
+/** Estimate token count using language-specific simple method. */
+export function estimateTokensMultilingualSimple(text: string): number;
+
+/** Estimate token count of a JSON document from its keys, values and punctuation. */
+export function estimateTokensJson(text: string): number;
+
+/** Estimate token count of source code from identifiers, operators and indentation. */
+export function estimateTokensCode(text: string): number;
+
+/**
+ * Estimate many texts on the libuv thread pool, off the main event loop.
+ * Throws for an unknown method.
+ */
+export function estimateTokensBatch(texts: string[], method?: Method | null): Promise<number[]>;
+
+export interface BasicCounts {
+  charCount: number;
+  wordCount: number;
+  avgWordLength: number;
+  spaceCount: number;
+}
+
+export interface MultilingualCounts extends BasicCounts {
+  language: string;
+}
+
+export interface MultilingualSimpleCounts {
+  charCount: number;
+  language: string;
+}
+
+/** Extract character count for simple method. */
+export function countSimple(text: string): number;
+
+/** Extract features for basic method. */
+export function countBasic(text: string): BasicCounts;
+
+/** Extract features for multilingual method. */
+export function countMultilingual(text: string): MultilingualCounts;
+
+/** Extract features for multilingual simple method. */
+export function countMultilingualSimple(text: string): MultilingualSimpleCounts;
+
+/** Detect the ISO 639-3 language code of text, or "unknown". */
+export function detectLanguage(text: string): string;
+
+/** Detect the languages of many texts on the libuv thread pool. */
+export function detectLanguageBatch(texts: string[]): Promise<string[]>;
@@ -0,0 +1,2 @@
+// Native addon built from src/lib.rs with `--features node`; see package.json
+module.exports = require("./skimtoken.node");
@@ -0,0 +1,38 @@
+const test = require("node:test");
+const assert = require("node:assert");
+const skimtoken = require("./index.js");
+
+test("estimates text with every method", () => {
+  const text = "Hello, world! This is a test.";
+  assert.ok(skimtoken.estimateTokens(text) > 0);
+  assert.strictEqual(skimtoken.estimateTokens(text), skimtoken.estimateTokensMultilingualSimple(text));
+  assert.strictEqual(skimtoken.estimateTokens(text, "basic"), skimtoken.estimateTokensBasic(text));
+  for (const estimate of [
+    skimtoken.estimateTokensSimple,
+    skimtoken.estimateTokensMultilingual,
+    skimtoken.estimateTokensJson,
+    skimtoken.estimateTokensCode,
+  ]) {
+    assert.ok(estimate(text) > 0);
+  }
+  assert.strictEqual(skimtoken.estimateTokens(""), 0);
+  assert.throws(() => skimtoken.estimateTokens(text, "bpe"), /unknown method: bpe/);
+});
+
+test("counts features", () => {
+  assert.strictEqual(skimtoken.countSimple("hello"), 5);
+  const basic = skimtoken.countBasic("hello big world");
+  assert.deepStrictEqual([basic.charCount, basic.wordCount, basic.spaceCount], [15, 3, 2]);
+  assert.strictEqual(skimtoken.countMultilingual("The quick brown fox jumps over the lazy dog").language, "eng");
+  assert.strictEqual(skimtoken.countMultilingualSimple("こんにちは、世界。今日はいい天気ですね。").language, "jpn");
+  assert.strictEqual(skimtoken.detectLanguage("Bonjour tout le monde, comment allez-vous ?"), "fra");
+});
+
+test("batches run asynchronously and match single calls", async () => {
+  const texts = ["first text", "一段落目の文章です。", "{\"key\": [1, 2, 3]}"];
+  const pending = skimtoken.estimateTokensBatch(texts, "json");
+  assert.ok(pending instanceof Promise);
+  assert.deepStrictEqual(await pending, texts.map((text) => skimtoken.estimateTokens(text, "json")));
+  assert.deepStrictEqual(await skimtoken.estimateTokensBatch([]), []);
+  assert.deepStrictEqual(await skimtoken.detectLanguageBatch(texts.slice(0, 2)), texts.slice(0, 2).map(skimtoken.detectLanguage));
+});
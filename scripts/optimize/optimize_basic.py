@@ -5,7 +5,6 @@ from pathlib import Path
 
 import numpy as np
 import numpy.typing as npt
-import toml
 from sklearn.linear_model import Ridge  # type: ignore[import-untyped]
 
 from scripts.optimize.utils import (
@@ -13,6 +12,7 @@ from scripts.optimize.utils import (
     filter_outliers,
     load_dataset_with_texts,
     print_metrics,
+    save_to_bundle,
 )
 from skimtoken.basic import count
 
@@ -110,7 +110,7 @@ def main() -> None:
         "--max-samples", type=int, default=None, help="Maximum number of samples to use"
     )
     parser.add_argument(
-        "--output", type=Path, default=Path("params/basic.toml"), help="Output path for parameters"
+        "--output", type=Path, default=Path("params/bundle.toml"), help="Output path for parameters"
     )
 
     args = parser.parse_args()
@@ -118,10 +118,8 @@ def main() -> None:
     # Optimize parameters
     params = optimize_parameters(args.dataset, args.val_dataset, args.max_samples)
 
-    # Save to TOML file
-    args.output.parent.mkdir(parents=True, exist_ok=True)
-    with open(args.output, "w") as f:
-        toml.dump(params, f)
+    # Save into the parameter bundle
+    save_to_bundle(args.output, "basic", params)
 
     print(f"\nParameters saved to {args.output}")
 
@@ -7,10 +7,14 @@ from typing import Any
 
 import numpy as np
 import numpy.typing as npt
-import toml
 from sklearn.linear_model import Ridge  # type: ignore[import-untyped]
 
-from scripts.optimize.utils import calculate_metrics, filter_outliers, load_dataset
+from scripts.optimize.utils import (
+    calculate_metrics,
+    filter_outliers,
+    load_dataset,
+    save_to_bundle,
+)
 from skimtoken.multilingual import count
 
 # Mapping from whatlang detected languages to CC100 language codes
@@ -329,7 +333,7 @@ def main() -> None:
     parser.add_argument(
         "--output",
         type=Path,
-        default=Path("params/multilingual.toml"),
+        default=Path("params/bundle.toml"),
         help="Output path for parameters",
     )
 
@@ -340,10 +344,8 @@ def main() -> None:
         args.dataset, args.val_dataset, args.max_samples, args.min_samples_per_lang
     )
 
-    # Save to TOML file
-    args.output.parent.mkdir(parents=True, exist_ok=True)
-    with open(args.output, "w") as f:
-        toml.dump(params, f)
+    # Save into the parameter bundle
+    save_to_bundle(args.output, "multilingual", params)
 
     print(f"\nParameters saved to {args.output}")
 
@@ -4,10 +4,15 @@ import argparse
 from pathlib import Path
 
 import numpy as np
-import toml
 from scipy.optimize import minimize_scalar  # type: ignore[import-untyped]
 
-from scripts.optimize.utils import calculate_metrics, filter_outliers, load_dataset, print_metrics
+from scripts.optimize.utils import (
+    calculate_metrics,
+    filter_outliers,
+    load_dataset,
+    print_metrics,
+    save_to_bundle,
+)
 from skimtoken.simple import count
 
 
@@ -108,7 +113,7 @@ def main() -> None:
         "--max-samples", type=int, default=None, help="Maximum number of samples to use"
     )
     parser.add_argument(
-        "--output", type=Path, default=Path("params/simple.toml"), help="Output path for parameters"
+        "--output", type=Path, default=Path("params/bundle.toml"), help="Output path for parameters"
     )
 
     args = parser.parse_args()
@@ -116,10 +121,8 @@ def main() -> None:
     # Optimize parameters
     params = optimize_parameters(args.dataset, args.val_dataset, args.max_samples)
 
-    # Save to TOML file
-    args.output.parent.mkdir(parents=True, exist_ok=True)
-    with open(args.output, "w") as f:
-        toml.dump(params, f)
+    # Save into the parameter bundle
+    save_to_bundle(args.output, "simple", params)
 
     print(f"\nParameters saved to {args.output}")
 
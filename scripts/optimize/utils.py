@@ -6,6 +6,7 @@ from pathlib import Path
 from typing import Any
 
 import numpy as np
+import toml
 
 
 def load_dataset(path: Path, max_samples: int | None = None) -> list[dict[str, Any]]:
@@ -20,6 +21,15 @@ def load_dataset(path: Path, max_samples: int | None = None) -> list[dict[str, A
     return data
 
 
+def save_to_bundle(path: Path, method: str, params: dict[str, Any]) -> None:
+    """Write parameters into the method's section of the parameter bundle."""
+    bundle: dict[str, Any] = dict(toml.load(path)) if path.exists() else {}
+    bundle[method] = params
+    path.parent.mkdir(parents=True, exist_ok=True)
+    with open(path, "w") as f:
+        toml.dump(bundle, f)
+
+
 def load_dataset_with_texts(
     path: Path, max_samples: int | None = None
 ) -> tuple[list[str], list[int]]:
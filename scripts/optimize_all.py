@@ -103,14 +103,9 @@ def main() -> None:
 
     if success_count == len(methods_to_run):
         print("\nAll optimizations completed successfully!")
-        print("\nOptimized parameters have been saved to:")
-        for method in methods_to_run:
-            print(f"  - params/{method}.toml")
-
-        print("\nTo use the optimized parameters, simply run:")
-        print("  import skimtoken")
-        print("  tokens = skimtoken.estimate_tokens('your text', 'simple')")
-        print("\nThe library will automatically load the optimized parameters.")
+        print("\nOptimized parameters have been saved to params/bundle.toml")
+        print("\nRebuild to compile them in as the defaults:")
+        print("  uv run maturin develop --release")
     else:
         print("\nSome optimizations failed. Please check the error messages above.")
         sys.exit(1)
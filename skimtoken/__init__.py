@@ -10,6 +10,8 @@ Full import:
     skimtoken.estimate_tokens(text)
 """
 
+from __future__ import annotations
+
 # Import from the Rust module
 from ._skimtoken_core import (
     estimate_tokens,
@@ -17,6 +19,18 @@ from ._skimtoken_core import (
     estimate_tokens_basic,
     estimate_tokens_multilingual,
     estimate_tokens_multilingual_simple,
+    estimate_tokens_json,
+    estimate_tokens_code,
+    estimate_chat_tokens,
+    estimate_tool_tokens,
+    estimate_markdown_tokens,
+    markdown_breakdown,
+    estimate_html_tokens,
+    strip_html,
+    truncate_to_tokens,
+    chunk,
+    estimate_tokens_sampled,
+    StreamingEstimator,
     count_simple,
     count_basic,
     count_multilingual,
@@ -32,6 +46,18 @@ __all__ = [
     "estimate_tokens_basic",
     "estimate_tokens_multilingual",
     "estimate_tokens_multilingual_simple",
+    "estimate_tokens_json",
+    "estimate_tokens_code",
+    "estimate_chat_tokens",
+    "estimate_tool_tokens",
+    "estimate_markdown_tokens",
+    "markdown_breakdown",
+    "estimate_html_tokens",
+    "strip_html",
+    "truncate_to_tokens",
+    "chunk",
+    "estimate_tokens_sampled",
+    "StreamingEstimator",
     "count_simple",
     "count_basic",
     "count_multilingual",
@@ -47,14 +73,103 @@ __all__ = [
 from . import simple, basic, multilingual, multilingual_simple
 
 
-# CLI entry point
-def main():
+def _read_text(file: str | None) -> str:
+    """Read text from a file, or from stdin without one."""
+    import sys
+
+    if not file:
+        return sys.stdin.read()
+    try:
+        with open(file, "r", encoding="utf-8") as f:
+            return f.read()
+    except Exception as e:
+        print(f"Error reading file: {e}", file=sys.stderr)
+        sys.exit(1)
+
+
+def _chat(argv: list[str]) -> None:
+    """Estimate prompt tokens of an OpenAI-style chat request."""
+    import argparse
+    import sys
+
+    parser = argparse.ArgumentParser(
+        prog="skimtoken chat",
+        description="Estimate prompt tokens of an OpenAI-style chat request, including tools",
+    )
+    parser.add_argument(
+        "-f", "--file", type=str, help="Read the request from file instead of stdin"
+    )
+    args = parser.parse_args(argv)
+
+    try:
+        print(estimate_chat_tokens(_read_text(args.file)))
+    except ValueError as e:
+        print(f"Error parsing chat request: {e}", file=sys.stderr)
+        sys.exit(1)
+
+
+def _split(argv: list[str]) -> None:
+    """Split a document into chunk files estimated under a token budget."""
+    import argparse
+    import os
+    import sys
+
+    parser = argparse.ArgumentParser(
+        prog="skimtoken split",
+        description="Split a document into chunks estimated under a token budget",
+        formatter_class=argparse.ArgumentDefaultsHelpFormatter,
+    )
+    parser.add_argument(
+        "-f", "--file", type=str, help="Read the document from file instead of stdin"
+    )
+    parser.add_argument(
+        "-o",
+        "--output",
+        required=True,
+        help="Directory to write chunk-0001.txt, chunk-0002.txt, ... to",
+    )
+    parser.add_argument("--max-tokens", type=int, required=True, help="Largest estimate of a chunk")
+    parser.add_argument(
+        "--overlap", type=int, default=0, help="Tokens repeated at the start of the next chunk"
+    )
+    parser.add_argument(
+        "--margin",
+        type=float,
+        default=0.0,
+        help="Share of --max-tokens held back for estimation error, e.g. 0.15",
+    )
+    args = parser.parse_args(argv)
+
+    chunks = chunk(_read_text(args.file), args.max_tokens, args.overlap, args.margin)
+    try:
+        os.makedirs(args.output, exist_ok=True)
+        for i, text in enumerate(chunks, 1):
+            path = os.path.join(args.output, f"chunk-{i:04}.txt")
+            with open(path, "w", encoding="utf-8") as f:
+                f.write(text)
+            print(f"{estimate_tokens(text):>8}  {path}")
+    except OSError as e:
+        print(f"Error writing chunks: {e}", file=sys.stderr)
+        sys.exit(1)
+    print(f"Wrote {len(chunks)} chunks to {args.output}", file=sys.stderr)
+
+
+# CLI entry point. The Rust binary has more subcommands and options; this
+# covers plain estimation, chat and split
+def main(argv: list[str] | None = None):
     """CLI entry point for skimtoken."""
     import argparse
     import sys
 
+    if argv is None:
+        argv = sys.argv[1:]
+    if argv[:1] == ["chat"]:
+        return _chat(argv[1:])
+    if argv[:1] == ["split"]:
+        return _split(argv[1:])
+
     parser = argparse.ArgumentParser(
-        description="Estimate token count for text",
+        description="Estimate token count for text. Subcommands: chat, split",
         formatter_class=argparse.ArgumentDefaultsHelpFormatter,
     )
 
@@ -71,16 +186,11 @@ def main():
         help="Read text from file instead of command line",
     )
 
-    args = parser.parse_args()
+    args = parser.parse_args(argv)
 
     # Get text from appropriate source
     if args.file:
-        try:
-            with open(args.file, "r", encoding="utf-8") as f:
-                text = f.read()
-        except Exception as e:
-            print(f"Error reading file: {e}", file=sys.stderr)
-            sys.exit(1)
+        text = _read_text(args.file)
     elif args.text:
         text = args.text
     else:
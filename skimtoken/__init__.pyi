@@ -1,6 +1,6 @@
 """Type stubs for skimtoken."""
 
-from typing import Literal, TYPE_CHECKING
+from typing import Any, TYPE_CHECKING
 
 __version__: str
 
@@ -8,11 +8,8 @@ __version__: str
 if TYPE_CHECKING:
     pass
 
-def estimate_tokens(
-    text: str,
-    method: Literal["simple", "basic", "multilingual", "multilingual_simple"] | None = None,
-) -> int:
-    """Estimate token count for text using specified method."""
+def estimate_tokens(text: str) -> int:
+    """Estimate token count for text using the multilingual_simple method."""
     ...
 
 def estimate_tokens_simple(text: str) -> int:
@@ -23,14 +20,125 @@ def estimate_tokens_basic(text: str) -> int:
     """Estimate token count using basic features (chars, words, etc.)."""
     ...
 
-def estimate_tokens_multilingual(text: str) -> int:
-    """Estimate token count using language-specific parameters."""
+def estimate_tokens_multilingual(text: str, language: str | None = None) -> int:
+    """Estimate token count using language-specific parameters.
+
+    language is an ISO 639-1, ISO 639-3 or BCP-47 tag ("ja", "jpn", "ja-JP")
+    used instead of detecting the language; unknown tags raise ValueError.
+    """
+    ...
+
+def estimate_tokens_multilingual_simple(text: str, language: str | None = None) -> int:
+    """Estimate token count using language-specific simple method.
+
+    language is an ISO 639-1, ISO 639-3 or BCP-47 tag ("ja", "jpn", "ja-JP")
+    used instead of detecting the language; unknown tags raise ValueError.
+    """
+    ...
+
+def estimate_tokens_json(text: str) -> int:
+    """Estimate token count of a JSON document from its keys, values and punctuation."""
+    ...
+
+def estimate_tokens_code(text: str) -> int:
+    """Estimate token count of source code from identifiers, operators and indentation."""
+    ...
+
+def estimate_chat_tokens(messages: str | list[dict[str, Any]] | dict[str, Any]) -> int:
+    """Estimate prompt tokens of OpenAI-style chat messages, including framing overhead.
+
+    Accepts a list of messages, a request dict with a "messages" key, or its JSON string.
+    Tool definitions under a "tools" key are counted as well.
+    """
+    ...
+
+def estimate_tool_tokens(tools: str | list[dict[str, Any]] | dict[str, Any]) -> int:
+    """Estimate prompt tokens consumed by tool/function definitions.
+
+    Accepts a list of tools, a request dict with a "tools" key, or its JSON string.
+    """
+    ...
+
+def estimate_markdown_tokens(text: str) -> int:
+    """Estimate Markdown block by block, using the code method for code blocks and tables."""
+    ...
+
+def markdown_breakdown(text: str) -> list[dict[str, Any]]:
+    """Per-block estimates of a Markdown document.
+
+    Each block has "kind" (heading, paragraph, list, table or code), "section"
+    (nearest heading), 1-based inclusive "start_line" and "end_line", and "tokens".
+    """
+    ...
+
+def estimate_html_tokens(text: str) -> tuple[int, int, int, int]:
+    """Estimate an HTML or XML document.
+
+    Returns (raw, markup, text, stripped): the raw document estimate, split into
+    markup and text nodes, and the estimate of the visible text after stripping.
+    """
     ...
 
-def estimate_tokens_multilingual_simple(text: str) -> int:
-    """Estimate token count using language-specific simple method."""
+def strip_html(text: str) -> str:
+    """Visible text of HTML or XML, without tags, comments, scripts and styles."""
     ...
 
+def truncate_to_tokens(text: str, max_tokens: int, side: str = "head") -> str:
+    """Truncate text to at most max_tokens estimated tokens, at word and preferably sentence boundaries.
+
+    side is "head" (keep the beginning), "tail" (keep the end) or "middle"
+    (keep both, joined by " … "). Text that fits is returned unchanged.
+    """
+    ...
+
+def chunk(text: str, max_tokens: int, overlap: int = 0, margin: float = 0.0) -> list[str]:
+    """Split text into chunks estimated under max_tokens, at paragraph, sentence or word boundaries.
+
+    About overlap tokens (at most half a chunk) are repeated at the start of
+    the next chunk. margin holds back that share of max_tokens for estimation
+    error, e.g. 0.15 sizes chunks to 85% of the limit.
+    """
+    ...
+
+def estimate_tokens_sampled(
+    text: str, windows: int = 32, window_bytes: int = 4096
+) -> tuple[int, float]:
+    """Estimate very large text from windows spread evenly across it.
+
+    Returns the extrapolated estimate and its standard error. Text no longer
+    than the windows together is estimated in full, with an error of 0.
+    Raises ValueError when window_bytes is 0.
+    """
+    ...
+
+class StreamingEstimator:
+    """Running estimate of growing text, e.g. streamed LLM output.
+
+    Each push costs time proportional to the pushed chunk. The language is
+    detected from the first 1024 bytes. push_bytes decodes `encoding`
+    ("utf-8", "utf-16", "utf-16le", "utf-16be" or "latin-1"); a byte order
+    mark overrides it, except for latin-1. Invalid input is replaced with
+    U+FFFD ("lossy"), counted as one token per byte ("bytes"), dropped
+    ("skip") or raises ValueError ("error").
+    """
+
+    def __init__(self, invalid: str = "lossy", encoding: str = "utf-8") -> None: ...
+    def push(self, text: str) -> None:
+        """Append the next chunk of text."""
+        ...
+    def push_bytes(self, data: bytes) -> None:
+        """Append raw bytes; a char split across calls is completed by the next call."""
+        ...
+    def estimate(self) -> int:
+        """Estimate of everything pushed so far."""
+        ...
+    def finish(self) -> int:
+        """End the stream; a dangling incomplete char is invalid."""
+        ...
+    def __len__(self) -> int:
+        """Bytes of text pushed so far."""
+        ...
+
 def count_simple(text: str) -> int:
     """Extract character count for simple method."""
     ...
@@ -51,8 +159,8 @@ def detect_language(text: str) -> str:
     """Detect language of text using whatlang."""
     ...
 
-def main() -> None:
-    """CLI entry point."""
+def main(argv: list[str] | None = None) -> None:
+    """CLI entry point, with the chat and split subcommands."""
     ...
 
 __all__: list[str]
@@ -1,7 +1,11 @@
 """Type stubs for skimtoken.multilingual module."""
 
-def estimate_tokens(text: str) -> int:
-    """Estimate token count using language-specific parameters."""
+def estimate_tokens(text: str, language: str | None = None) -> int:
+    """Estimate token count using language-specific parameters.
+
+    language is an ISO 639-1, ISO 639-3 or BCP-47 tag ("ja", "jpn", "ja-JP")
+    used instead of detecting the language; unknown tags raise ValueError.
+    """
     ...
 
 def count(text: str) -> tuple[int, int, float, int, str]:
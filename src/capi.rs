@@ -0,0 +1,189 @@
+// C ABI for callers such as Go (cgo) and C++. The build script generates
+// `include/skimtoken.h` from this file.
+
+use crate::{Method, ParameterBundle, TextEstimator, DEFAULT_METHOD};
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+/// Returned by `skimtoken_estimate` on failure
+pub const SKIMTOKEN_ERROR: usize = usize::MAX;
+
+/// Estimator for one method and parameter set; shareable across threads
+pub struct SkimtokenEstimator {
+    estimator: Box<dyn TextEstimator + Send + Sync>,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_error(message: impl Into<Vec<u8>>) {
+    let message = CString::new(message).unwrap_or_else(|_| c"invalid error message".into());
+    LAST_ERROR.with(|error| *error.borrow_mut() = Some(message));
+}
+
+fn clear_error() {
+    LAST_ERROR.with(|error| *error.borrow_mut() = None);
+}
+
+/// Optional C string argument: NULL is `None`
+///
+/// # Safety
+/// `ptr` is NULL or a NUL-terminated string
+unsafe fn optional_str<'a>(ptr: *const c_char, name: &str) -> Result<Option<&'a str>, String> {
+    if ptr.is_null() {
+        return Ok(None);
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map(Some)
+        .map_err(|_| format!("{name} is not valid UTF-8"))
+}
+
+/// Create an estimator for `method` (e.g. "multilingual_simple"), with
+/// parameters from the TOML bundle in `params_toml`. Either may be NULL for
+/// the default method and the compiled-in parameters. Returns NULL on error,
+/// see `skimtoken_last_error`.
+///
+/// # Safety
+/// `method` and `params_toml` are NULL or NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn skimtoken_estimator_new(
+    method: *const c_char,
+    params_toml: *const c_char,
+) -> *mut SkimtokenEstimator {
+    clear_error();
+    let result = catch_unwind(|| -> Result<SkimtokenEstimator, String> {
+        let method = match optional_str(method, "method")? {
+            Some(name) => name.parse::<Method>()?,
+            None => DEFAULT_METHOD,
+        };
+        let bundle = match optional_str(params_toml, "params_toml")? {
+            Some(toml) => {
+                ParameterBundle::from_toml_str(toml).map_err(|e| format!("invalid params: {e}"))?
+            }
+            None => ParameterBundle::default(),
+        };
+        Ok(SkimtokenEstimator {
+            estimator: bundle.text_estimator(method),
+        })
+    });
+    match result {
+        Ok(Ok(estimator)) => Box::into_raw(Box::new(estimator)),
+        Ok(Err(message)) => {
+            set_error(message);
+            ptr::null_mut()
+        }
+        Err(_) => {
+            set_error("panic while creating the estimator");
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Estimate the tokens of `len` bytes of UTF-8 text at `ptr`; invalid UTF-8
+/// is replaced with U+FFFD. Returns `SKIMTOKEN_ERROR` on error, see
+/// `skimtoken_last_error`.
+///
+/// # Safety
+/// `handle` comes from `skimtoken_estimator_new` and was not freed; `ptr`
+/// points to `len` readable bytes, or `len` is 0.
+#[no_mangle]
+pub unsafe extern "C" fn skimtoken_estimate(
+    handle: *const SkimtokenEstimator,
+    ptr: *const u8,
+    len: usize,
+) -> usize {
+    clear_error();
+    let Some(handle) = handle.as_ref() else {
+        set_error("handle is NULL");
+        return SKIMTOKEN_ERROR;
+    };
+    if len == 0 {
+        return 0;
+    }
+    if ptr.is_null() {
+        set_error("text is NULL");
+        return SKIMTOKEN_ERROR;
+    }
+    let bytes = std::slice::from_raw_parts(ptr, len);
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        handle
+            .estimator
+            .estimate_text(&String::from_utf8_lossy(bytes))
+    }));
+    result.unwrap_or_else(|_| {
+        set_error("panic while estimating");
+        SKIMTOKEN_ERROR
+    })
+}
+
+/// Free an estimator; NULL is ignored.
+///
+/// # Safety
+/// `handle` comes from `skimtoken_estimator_new` and is not used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn skimtoken_estimator_free(handle: *mut SkimtokenEstimator) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+/// Message of the last error on this thread, or NULL. The string stays valid
+/// until the next skimtoken call on the same thread.
+#[no_mangle]
+pub extern "C" fn skimtoken_last_error() -> *const c_char {
+    LAST_ERROR.with(|error| {
+        error
+            .borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn last_error() -> String {
+        unsafe { CStr::from_ptr(skimtoken_last_error()) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn test_estimate_through_handle() {
+        let text = "Hello from C, こんにちは";
+        unsafe {
+            let handle = skimtoken_estimator_new(c"basic".as_ptr(), ptr::null());
+            assert!(!handle.is_null());
+            let tokens = skimtoken_estimate(handle, text.as_ptr(), text.len());
+            assert_eq!(
+                tokens,
+                ParameterBundle::default()
+                    .text_estimator(Method::Basic)
+                    .estimate_text(text)
+            );
+            assert!(skimtoken_last_error().is_null());
+            skimtoken_estimator_free(handle);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        unsafe {
+            assert!(skimtoken_estimator_new(c"bpe".as_ptr(), ptr::null()).is_null());
+            assert_eq!(last_error(), "unknown method: bpe");
+            let params = c"[simple]\ncoefficient = \"high\"";
+            assert!(skimtoken_estimator_new(ptr::null(), params.as_ptr()).is_null());
+            assert!(last_error().starts_with("invalid params"));
+            assert_eq!(
+                skimtoken_estimate(ptr::null(), ptr::null(), 0),
+                SKIMTOKEN_ERROR
+            );
+            assert_eq!(last_error(), "handle is NULL");
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use crate::params::ParameterBundle;
+use crate::tools::{ToolDefinition, ToolEstimator};
+use crate::{Method, DEFAULT_METHOD};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+/// One message of an OpenAI-style chat request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: String,
+    #[serde(default)]
+    pub content: Option<MessageContent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// Message content, either a plain string or a list of typed parts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MessageContent {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContentPart {
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Only text parts are counted; images and other parts are ignored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+/// Framing overhead the tokenizer's chat template adds around messages
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatParameters {
+    /// Tokens added for every message (role markers and separators)
+    pub per_message: f32,
+    /// Extra tokens when a message carries a `name`
+    pub per_name: f32,
+    /// Tokens added once per conversation (reply priming)
+    pub per_conversation: f32,
+}
+
+impl Default for ChatParameters {
+    fn default() -> Self {
+        // Generated from params/bundle.toml by build.rs
+        include!(concat!(env!("OUT_DIR"), "/params/chat.rs"))
+    }
+}
+
+/// The parts of an OpenAI-style chat request that end up in the prompt
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChatRequest {
+    pub messages: Vec<ChatMessage>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<ToolDefinition>,
+}
+
+/// Parse a chat request body, or a bare JSON array of messages
+pub fn parse_chat_request(json: &str) -> Result<ChatRequest, Box<dyn Error>> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Body {
+        Request(ChatRequest),
+        Messages(Vec<ChatMessage>),
+    }
+
+    Ok(match serde_json::from_str(json)? {
+        Body::Request(request) => request,
+        Body::Messages(messages) => ChatRequest {
+            messages,
+            tools: Vec::new(),
+        },
+    })
+}
+
+/// Parse the messages of a chat request body, or a bare JSON array of messages
+pub fn parse_chat_messages(json: &str) -> Result<Vec<ChatMessage>, Box<dyn Error>> {
+    Ok(parse_chat_request(json)?.messages)
+}
+
+/// Estimates prompt tokens of chat conversations
+pub struct ChatEstimator {
+    parameters: ChatParameters,
+    estimator: Box<dyn Fn(&str) -> usize + Send + Sync>,
+    tools: ToolEstimator,
+}
+
+impl ChatEstimator {
+    /// Estimator using the compiled-in parameters and the default text method
+    pub fn new() -> Self {
+        Self::from_bundle(&ParameterBundle::default(), DEFAULT_METHOD)
+    }
+
+    pub fn from_bundle(bundle: &ParameterBundle, method: Method) -> Self {
+        Self {
+            parameters: bundle.chat.clone(),
+            estimator: bundle.estimator(method),
+            tools: ToolEstimator::from_bundle(bundle, method),
+        }
+    }
+
+    /// Estimate a full request: its messages plus any tool definitions
+    pub fn estimate_request(&self, request: &ChatRequest) -> usize {
+        self.estimate(&request.messages) + self.tools.estimate(&request.tools)
+    }
+
+    pub fn estimate(&self, messages: &[ChatMessage]) -> usize {
+        let params = &self.parameters;
+        let mut total = params.per_conversation;
+
+        for message in messages {
+            total += params.per_message;
+            total += (self.estimator)(&message.role) as f32;
+            match &message.content {
+                Some(MessageContent::Text(text)) => total += (self.estimator)(text) as f32,
+                Some(MessageContent::Parts(parts)) => {
+                    for text in parts.iter().filter_map(|part| part.text.as_deref()) {
+                        total += (self.estimator)(text) as f32;
+                    }
+                }
+                None => {}
+            }
+            if let Some(name) = &message.name {
+                total += params.per_name + (self.estimator)(name) as f32;
+            }
+        }
+
+        total.round().max(0.0) as usize
+    }
+}
+
+impl Default for ChatEstimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request_and_bare_messages() {
+        let request = r#"{"model": "gpt-4o", "messages": [
+            {"role": "system", "content": "You are helpful."},
+            {"role": "user", "name": "alice", "content": [{"type": "text", "text": "Hi"}]}
+        ]}"#;
+        let messages = parse_chat_messages(request).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].name.as_deref(), Some("alice"));
+
+        let bare = r#"[{"role": "user", "content": "Hi"}]"#;
+        assert_eq!(parse_chat_messages(bare).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_request_with_tools() {
+        let request = parse_chat_request(
+            r#"{"messages": [{"role": "user", "content": "Weather in Paris?"}],
+                "tools": [{"type": "function", "function": {"name": "get_weather"}}]}"#,
+        )
+        .unwrap();
+        let estimator = ChatEstimator::new();
+        assert!(estimator.estimate_request(&request) > estimator.estimate(&request.messages));
+    }
+
+    #[test]
+    fn test_framing_overhead() {
+        let estimator = ChatEstimator::new();
+        let empty = estimator.estimate(&[]);
+        let params = ChatParameters::default();
+        assert_eq!(empty, params.per_conversation.round() as usize);
+
+        let messages = parse_chat_messages(r#"[{"role": "user", "content": ""}]"#).unwrap();
+        assert!(
+            estimator.estimate(&messages) as f32 >= params.per_conversation + params.per_message
+        );
+    }
+}
@@ -0,0 +1,233 @@
+use crate::methods::method::TextEstimator;
+use crate::truncate::{char_end, ends_sentence, fits_budget, last_fitting, tail_start};
+
+/// Options controlling how `chunk_with` splits text
+#[derive(Debug, Clone)]
+pub struct ChunkOptions {
+    /// Largest estimate of a chunk; 0 is treated as 1, as no chunk can be
+    /// smaller than one token
+    pub max_tokens: usize,
+    /// Tokens at the end of a chunk repeated at the start of the next one,
+    /// capped at half of the chunk budget
+    pub overlap: usize,
+    /// Share of `max_tokens` held back for estimation error; 0.15 sizes chunks
+    /// to 85% of the limit
+    pub margin: f64,
+}
+
+impl ChunkOptions {
+    pub fn new(max_tokens: usize) -> Self {
+        Self {
+            max_tokens,
+            overlap: 0,
+            margin: 0.0,
+        }
+    }
+
+    /// Estimate each chunk is sized to, at least one token
+    pub fn budget(&self) -> usize {
+        let budget = self.max_tokens as f64 * (1.0 - self.margin.clamp(0.0, 1.0));
+        (budget.floor() as usize).max(1)
+    }
+}
+
+/// Longest sample an accumulator is set up from, e.g. to detect the language
+const SAMPLE_BYTES: usize = 4096;
+
+/// A paragraph or sentence boundary is preferred over a later cut if the chunk
+/// keeps at least this share of its length
+const BOUNDARY_SHARE: f64 = 0.5;
+
+/// Boundaries of each kind tried before falling back to a later one
+const MAX_TRIES: usize = 4;
+
+/// Split `text` into chunks estimated at most `options.budget()` tokens each,
+/// cutting at paragraph, then sentence, then word boundaries. Chunks are
+/// slices of `text` without surrounding whitespace
+pub fn chunk_with<'t, M: TextEstimator + ?Sized>(
+    method: &M,
+    text: &'t str,
+    options: &ChunkOptions,
+) -> Vec<&'t str> {
+    let budget = options.budget();
+    let overlap = options.overlap.min(budget / 2);
+    let mut chunks = Vec::new();
+    let mut start = skip_whitespace(text, 0);
+    while start < text.len() {
+        let rest = &text[start..];
+        let mut end = chunk_end(method, rest, budget);
+
+        // Always make progress, even if a single char is over budget
+        if end == 0 {
+            end = rest.chars().next().map_or(rest.len(), char::len_utf8);
+        }
+
+        let chunk = &rest[..end];
+        chunks.push(chunk);
+        let next = skip_whitespace(text, start + end);
+        if next >= text.len() {
+            break;
+        }
+        let overlap_start = match overlap {
+            0 => chunk.len(),
+            _ => tail_start(method, chunk, overlap),
+        };
+        start = if overlap_start > 0 && overlap_start < chunk.len() {
+            start + overlap_start
+        } else {
+            next
+        };
+    }
+    chunks
+}
+
+fn skip_whitespace(text: &str, from: usize) -> usize {
+    text.len() - text[from..].trim_start().len()
+}
+
+/// Words of `text`, each with its trailing whitespace
+fn words(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let word = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let len = rest.len() - rest[word..].trim_start().len();
+        let (word, tail) = rest.split_at(len);
+        rest = tail;
+        Some(word)
+    })
+}
+
+/// End of the first chunk of `text`, without trailing whitespace
+fn chunk_end<M: TextEstimator + ?Sized>(method: &M, text: &str, budget: usize) -> usize {
+    let mut sample_len = SAMPLE_BYTES.min(text.len());
+    while !text.is_char_boundary(sample_len) {
+        sample_len -= 1;
+    }
+    let sample = &text[..sample_len];
+
+    let mut accumulator = method.text_accumulator(sample);
+    // End of each word pushed, trailing whitespace included
+    let mut ends = vec![0];
+    let mut paragraphs = Vec::new();
+    let mut sentences = Vec::new();
+    let mut cut = false;
+    for word in words(text) {
+        accumulator.push(word);
+        if accumulator.estimate() > budget {
+            cut = true;
+            break;
+        }
+        let end = ends[ends.len() - 1] + word.len();
+        ends.push(end);
+        if word[word.trim_end().len()..].matches('\n').count() >= 2 {
+            paragraphs.push(end);
+        } else if ends_sentence(word) {
+            sentences.push(end);
+        }
+    }
+    let end = ends[ends.len() - 1];
+    if end == 0 {
+        // The first word alone is over budget: cut inside it
+        return char_end(method, sample, text, budget);
+    }
+
+    // The accumulated estimate can drift from a full pass, e.g. around
+    // fences, so candidates are checked in order of preference
+    let fits = |end: usize| fits_budget(method, &text[..end], budget);
+    let keeps = |boundary: &&usize| **boundary as f64 >= end as f64 * BOUNDARY_SHARE;
+    let whole = (!cut).then_some(end);
+    let boundaries = [&paragraphs, &sentences]
+        .into_iter()
+        .flat_map(|ends| ends.iter().rev().filter(keeps).take(MAX_TRIES).copied());
+    if let Some(end) = whole.into_iter().chain(boundaries).find(|&end| fits(end)) {
+        return text[..end].trim_end().len();
+    }
+
+    let trimmed = |words: usize| text[..ends[words]].trim_end().len();
+    match last_fitting(ends.len() - 1, |words| fits(trimmed(words))) {
+        Some(words) if words > 0 => trimmed(words),
+        // A single word that is over budget: cut inside it
+        _ => char_end(method, sample, &text[..trimmed(1)], budget),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::methods::method::EstimationMethod;
+    use crate::{DefaultMethod, Method, ParameterBundle};
+
+    const DOC: &str = "The first paragraph has two sentences. This is the second one.\n\n\
+        The second paragraph is a little longer than the first. It has three sentences. \
+        The last of them closes it.\n\nA short closing paragraph.";
+
+    #[test]
+    fn test_chunks_fit_and_cover_text() {
+        let method = DefaultMethod::new();
+        for max_tokens in [3, 10, 25, 1000] {
+            let chunks = chunk_with(&method, DOC, &ChunkOptions::new(max_tokens));
+            assert!(chunks.iter().all(|c| method.estimate(c) <= max_tokens));
+            let joined: String = chunks.concat();
+            let expected: String = DOC.split_whitespace().collect();
+            assert_eq!(joined.split_whitespace().collect::<String>(), expected);
+        }
+    }
+
+    #[test]
+    fn test_unbroken_text_fits_for_every_method() {
+        let text = format!("{}{}", "x".repeat(100), "日本語の長い文字列".repeat(8));
+        let bundle = ParameterBundle::default();
+        for &method in Method::ALL {
+            let estimator = bundle.text_estimator(method);
+            for max_tokens in [1, 3, 10, 40] {
+                let chunks = chunk_with(estimator.as_ref(), &text, &ChunkOptions::new(max_tokens));
+                assert_eq!(chunks.concat(), text);
+                // Only a single char over budget may exceed it
+                assert!(
+                    chunks.iter().all(|c| c.chars().count() == 1
+                        || estimator.estimate_text(c) <= max_tokens),
+                    "{method} {max_tokens}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_prefers_paragraphs() {
+        let method = DefaultMethod::new();
+        let chunks = chunk_with(&method, DOC, &ChunkOptions::new(25));
+        assert_eq!(
+            chunks[0],
+            "The first paragraph has two sentences. This is the second one."
+        );
+    }
+
+    #[test]
+    fn test_overlap_and_margin() {
+        let method = DefaultMethod::new();
+        let options = ChunkOptions {
+            overlap: 4,
+            margin: 0.2,
+            ..ChunkOptions::new(20)
+        };
+        let chunks = chunk_with(&method, DOC, &options);
+        assert!(chunks.iter().all(|c| method.estimate(c) <= 16));
+        for pair in chunks.windows(2) {
+            let last_word = pair[0].split_whitespace().last().unwrap();
+            assert!(pair[1].contains(last_word));
+        }
+    }
+
+    #[test]
+    fn test_zero_max_tokens_means_one() {
+        let method = DefaultMethod::new();
+        let text = "One two three.";
+        assert_eq!(
+            chunk_with(&method, text, &ChunkOptions::new(0)),
+            chunk_with(&method, text, &ChunkOptions::new(1))
+        );
+    }
+}
@@ -0,0 +1,54 @@
+use crate::tools::ToolDefinition;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// One labeled example, in the JSONL format of data/test_dataset.jsonl
+#[derive(Debug, Clone, Deserialize)]
+pub struct Sample {
+    pub text: String,
+    pub token_len: usize,
+    #[serde(default)]
+    pub category: Option<String>,
+}
+
+/// A labeled set of tool definitions, for calibrating tool estimation
+#[derive(Debug, Clone, Deserialize)]
+pub struct ToolSample {
+    pub tools: Vec<ToolDefinition>,
+    pub token_len: usize,
+}
+
+/// Load labeled samples from a JSONL file, skipping blank lines and empty texts
+pub fn load_dataset(path: &Path) -> Result<Vec<Sample>, Box<dyn Error>> {
+    let mut samples: Vec<Sample> = read_jsonl(path)?;
+    samples.retain(|sample| !sample.text.trim().is_empty());
+    Ok(samples)
+}
+
+/// Load labeled tool definition sets from a JSONL file
+pub fn load_tool_dataset(path: &Path) -> Result<Vec<ToolSample>, Box<dyn Error>> {
+    let mut samples: Vec<ToolSample> = read_jsonl(path)?;
+    samples.retain(|sample| !sample.tools.is_empty());
+    Ok(samples)
+}
+
+fn read_jsonl<T: DeserializeOwned>(path: &Path) -> Result<Vec<T>, Box<dyn Error>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut items = Vec::new();
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let item = serde_json::from_str(&line)
+            .map_err(|e| format!("{}:{}: {e}", path.display(), i + 1))?;
+        items.push(item);
+    }
+
+    Ok(items)
+}
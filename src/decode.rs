@@ -0,0 +1,450 @@
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+/// Text encoding of byte input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    /// UTF-16 in the byte order given by the BOM, little endian without one
+    Utf16,
+    Utf16Le,
+    Utf16Be,
+    /// ISO 8859-1: every byte is the char of the same value, never invalid
+    Latin1,
+}
+
+impl Encoding {
+    pub const ALL: [Encoding; 5] = [
+        Encoding::Utf8,
+        Encoding::Utf16,
+        Encoding::Utf16Le,
+        Encoding::Utf16Be,
+        Encoding::Latin1,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "utf-8",
+            Encoding::Utf16 => "utf-16",
+            Encoding::Utf16Le => "utf-16le",
+            Encoding::Utf16Be => "utf-16be",
+            Encoding::Latin1 => "latin-1",
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Encoding {
+    type Err = String;
+
+    /// Names are matched ignoring case, `-` and `_`, so `UTF8` and `latin_1`
+    /// work too, as does `iso-8859-1`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalize = |name: &str| name.to_ascii_lowercase().replace(['-', '_'], "");
+        let wanted = normalize(s);
+        if wanted == "iso88591" {
+            return Ok(Encoding::Latin1);
+        }
+        Encoding::ALL
+            .into_iter()
+            .find(|encoding| normalize(encoding.name()) == wanted)
+            .ok_or_else(|| format!("unknown encoding: {s}"))
+    }
+}
+
+/// What to do with bytes that are invalid in the input encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InvalidPolicy {
+    /// Replace each invalid sequence with U+FFFD
+    #[default]
+    Lossy,
+    /// Count each invalid byte as one token, like byte-fallback tokenizers
+    Bytes,
+    /// Drop invalid bytes
+    Skip,
+    /// Fail with `io::ErrorKind::InvalidData`
+    Error,
+}
+
+impl InvalidPolicy {
+    pub const ALL: [InvalidPolicy; 4] = [
+        InvalidPolicy::Lossy,
+        InvalidPolicy::Bytes,
+        InvalidPolicy::Skip,
+        InvalidPolicy::Error,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            InvalidPolicy::Lossy => "lossy",
+            InvalidPolicy::Bytes => "bytes",
+            InvalidPolicy::Skip => "skip",
+            InvalidPolicy::Error => "error",
+        }
+    }
+}
+
+impl fmt::Display for InvalidPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for InvalidPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        InvalidPolicy::ALL
+            .into_iter()
+            .find(|policy| policy.name() == s)
+            .ok_or_else(|| format!("unknown invalid input policy: {s}"))
+    }
+}
+
+/// A piece of decoded input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decoded<'a> {
+    Text(&'a str),
+    /// Invalid bytes kept for counting under `InvalidPolicy::Bytes`
+    Invalid(usize),
+}
+
+const BOMS: [(&[u8], Encoding); 3] = [
+    (b"\xEF\xBB\xBF", Encoding::Utf8),
+    (b"\xFF\xFE", Encoding::Utf16Le),
+    (b"\xFE\xFF", Encoding::Utf16Be),
+];
+
+/// Encoding given by the byte order mark `bytes` start with, and its length
+pub fn sniff_bom(bytes: &[u8]) -> Option<(Encoding, usize)> {
+    BOMS.iter()
+        .find(|(bom, _)| bytes.starts_with(bom))
+        .map(|(bom, encoding)| (*encoding, bom.len()))
+}
+
+/// Incremental decoder: bytes can be split anywhere between calls, even
+/// inside a char. A byte order mark at the start overrides the encoding,
+/// except for Latin-1, and is dropped
+#[derive(Debug, Clone, Default)]
+pub struct Decoder {
+    encoding: Encoding,
+    invalid: InvalidPolicy,
+    sniffed: bool,
+    /// Bytes held back until the next call completes them
+    pending: Vec<u8>,
+    offset: usize,
+}
+
+impl Decoder {
+    pub fn new(encoding: Encoding, invalid: InvalidPolicy) -> Self {
+        Self {
+            encoding,
+            invalid,
+            ..Self::default()
+        }
+    }
+
+    /// Encoding in use, once a byte order mark has been looked for
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    pub fn invalid(&self) -> InvalidPolicy {
+        self.invalid
+    }
+
+    /// Input bytes decoded so far
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Decode `bytes`, handing the text to `sink` piece by piece. Only the few
+    /// bytes of a char split across calls are copied
+    pub fn push(&mut self, mut bytes: &[u8], sink: &mut dyn FnMut(Decoded<'_>)) -> io::Result<()> {
+        if !self.sniffed {
+            let take = 3usize.saturating_sub(self.pending.len()).min(bytes.len());
+            self.pending.extend_from_slice(&bytes[..take]);
+            bytes = &bytes[take..];
+            let maybe_bom = BOMS.iter().any(|(bom, _)| bom.starts_with(&self.pending));
+            if self.pending.len() < 3 && maybe_bom && self.encoding != Encoding::Latin1 {
+                return Ok(());
+            }
+            self.sniff();
+        }
+
+        // Complete what the last call left over; a char is at most 4 bytes
+        while !self.pending.is_empty() && !bytes.is_empty() {
+            let carried = self.pending.len();
+            let mut head = std::mem::take(&mut self.pending);
+            head.extend_from_slice(&bytes[..bytes.len().min(4 - carried)]);
+            let taken = head.len() - carried;
+            let left = self.decode(&head, sink)?;
+            if left > taken {
+                self.pending = head[head.len() - left..].to_vec();
+                bytes = &bytes[taken..];
+            } else {
+                bytes = &bytes[taken - left..];
+                break;
+            }
+        }
+        if !self.pending.is_empty() {
+            return Ok(());
+        }
+
+        let left = self.decode(bytes, sink)?;
+        self.pending = bytes[bytes.len() - left..].to_vec();
+        Ok(())
+    }
+
+    /// End of input: bytes still held back are decoded, or are invalid
+    pub fn finish(&mut self, sink: &mut dyn FnMut(Decoded<'_>)) -> io::Result<()> {
+        if !self.sniffed {
+            self.sniff();
+        }
+        let pending = std::mem::take(&mut self.pending);
+        let left = self.decode(&pending, sink)?;
+        if left > 0 {
+            self.reject(left, sink)?;
+        }
+        Ok(())
+    }
+
+    fn sniff(&mut self) {
+        self.sniffed = true;
+        if self.encoding == Encoding::Latin1 {
+            return;
+        }
+        if let Some((encoding, len)) = sniff_bom(&self.pending) {
+            self.encoding = encoding;
+            self.pending.drain(..len);
+            self.offset += len;
+        } else if self.encoding == Encoding::Utf16 {
+            self.encoding = Encoding::Utf16Le;
+        }
+    }
+
+    /// Decode `bytes` and return the length of an incomplete char at the end,
+    /// which is not consumed
+    fn decode(&mut self, bytes: &[u8], sink: &mut dyn FnMut(Decoded<'_>)) -> io::Result<usize> {
+        match self.encoding {
+            Encoding::Utf8 => self.decode_utf8(bytes, sink),
+            Encoding::Utf16 | Encoding::Utf16Le => self.decode_utf16(bytes, false, sink),
+            Encoding::Utf16Be => self.decode_utf16(bytes, true, sink),
+            Encoding::Latin1 => {
+                let text: String = bytes.iter().map(|&b| char::from(b)).collect();
+                self.text(&text, bytes.len(), sink);
+                Ok(0)
+            }
+        }
+    }
+
+    fn decode_utf8(
+        &mut self,
+        bytes: &[u8],
+        sink: &mut dyn FnMut(Decoded<'_>),
+    ) -> io::Result<usize> {
+        let mut rest = bytes;
+        loop {
+            let (valid, error) = match std::str::from_utf8(rest) {
+                Ok(text) => (text, None),
+                Err(e) => {
+                    // Checked by from_utf8 above
+                    let valid = std::str::from_utf8(&rest[..e.valid_up_to()]).unwrap_or_default();
+                    (valid, Some(e))
+                }
+            };
+            self.text(valid, valid.len(), sink);
+            let Some(error) = error else {
+                return Ok(0);
+            };
+            let after = &rest[valid.len()..];
+            match error.error_len() {
+                Some(len) => {
+                    self.reject(len, sink)?;
+                    rest = &after[len..];
+                }
+                None => return Ok(after.len()),
+            }
+        }
+    }
+
+    fn decode_utf16(
+        &mut self,
+        bytes: &[u8],
+        big_endian: bool,
+        sink: &mut dyn FnMut(Decoded<'_>),
+    ) -> io::Result<usize> {
+        let unit = |i: usize| {
+            let pair = [bytes[i], bytes[i + 1]];
+            if big_endian {
+                u16::from_be_bytes(pair)
+            } else {
+                u16::from_le_bytes(pair)
+            }
+        };
+        let mut text = String::new();
+        let mut start = 0;
+        let mut i = 0;
+        while i + 1 < bytes.len() {
+            let high = unit(i);
+            let c = match high {
+                0xD800..=0xDBFF if i + 3 >= bytes.len() => break,
+                0xD800..=0xDBFF => {
+                    let low = unit(i + 2);
+                    let c = 0x10000 + ((u32::from(high) - 0xD800) << 10);
+                    (0xDC00..=0xDFFF)
+                        .contains(&low)
+                        .then(|| char::from_u32(c + (u32::from(low) - 0xDC00)))
+                        .flatten()
+                }
+                _ => char::from_u32(u32::from(high)),
+            };
+            match c {
+                Some(c) => {
+                    text.push(c);
+                    i += if c.len_utf16() == 2 { 4 } else { 2 };
+                }
+                None => {
+                    // Unpaired surrogate
+                    self.text(&text, i - start, sink);
+                    text.clear();
+                    self.reject(2, sink)?;
+                    i += 2;
+                    start = i;
+                }
+            }
+        }
+        self.text(&text, i - start, sink);
+        Ok(bytes.len() - i)
+    }
+
+    fn text(&mut self, text: &str, input_len: usize, sink: &mut dyn FnMut(Decoded<'_>)) {
+        if !text.is_empty() {
+            sink(Decoded::Text(text));
+        }
+        self.offset += input_len;
+    }
+
+    fn reject(&mut self, len: usize, sink: &mut dyn FnMut(Decoded<'_>)) -> io::Result<()> {
+        match self.invalid {
+            InvalidPolicy::Lossy => sink(Decoded::Text("\u{FFFD}")),
+            InvalidPolicy::Bytes => sink(Decoded::Invalid(len)),
+            InvalidPolicy::Skip => {}
+            InvalidPolicy::Error => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "invalid {} at byte {}",
+                        self.encoding.name().to_uppercase(),
+                        self.offset
+                    ),
+                ))
+            }
+        }
+        self.offset += len;
+        Ok(())
+    }
+}
+
+/// Decode a whole buffer into its text and the number of invalid bytes left
+/// out of it, which only `InvalidPolicy::Bytes` reports. Each of them counts
+/// as a token
+pub fn decode_bytes(
+    bytes: &[u8],
+    encoding: Encoding,
+    invalid: InvalidPolicy,
+) -> io::Result<(String, usize)> {
+    let mut decoder = Decoder::new(encoding, invalid);
+    let mut text = String::with_capacity(bytes.len());
+    let mut invalid_bytes = 0;
+    let mut sink = |piece: Decoded<'_>| match piece {
+        Decoded::Text(piece) => text.push_str(piece),
+        Decoded::Invalid(len) => invalid_bytes += len,
+    };
+    decoder.push(bytes, &mut sink)?;
+    decoder.finish(&mut sink)?;
+    Ok((text, invalid_bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_split(bytes: &[u8], encoding: Encoding, invalid: InvalidPolicy) -> String {
+        // Byte by byte, so that every char is split across calls
+        let mut decoder = Decoder::new(encoding, invalid);
+        let mut text = String::new();
+        let mut sink = |piece: Decoded<'_>| match piece {
+            Decoded::Text(piece) => text.push_str(piece),
+            Decoded::Invalid(len) => text.push_str(&"?".repeat(len)),
+        };
+        for byte in bytes {
+            decoder.push(&[*byte], &mut sink).unwrap();
+        }
+        decoder.finish(&mut sink).unwrap();
+        text
+    }
+
+    #[test]
+    fn test_encodings_and_boms() {
+        let text = "héllo 日本 🎉";
+        let le: Vec<u8> = text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let be: Vec<u8> = text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+        let lossy = InvalidPolicy::Lossy;
+        assert_eq!(decode_split(text.as_bytes(), Encoding::Utf8, lossy), text);
+        assert_eq!(decode_split(&le, Encoding::Utf16Le, lossy), text);
+        assert_eq!(decode_split(&be, Encoding::Utf16Be, lossy), text);
+        assert_eq!(decode_split(&le, Encoding::Utf16, lossy), text);
+        assert_eq!(
+            decode_split(&[b"\xFE\xFF", &be[..]].concat(), Encoding::Utf8, lossy),
+            text
+        );
+        assert_eq!(
+            decode_split(
+                &[b"\xEF\xBB\xBF", text.as_bytes()].concat(),
+                Encoding::Utf16,
+                lossy
+            ),
+            text
+        );
+        assert_eq!(decode_split(b"caf\xE9", Encoding::Latin1, lossy), "café");
+        assert_eq!(decode_split(b"\xFF\xFE", Encoding::Latin1, lossy), "ÿþ");
+    }
+
+    #[test]
+    fn test_invalid_policies() {
+        let bytes = b"a\xFFb\xE6\x97";
+        assert_eq!(
+            decode_split(bytes, Encoding::Utf8, InvalidPolicy::Lossy),
+            "a\u{FFFD}b\u{FFFD}"
+        );
+        assert_eq!(
+            decode_split(bytes, Encoding::Utf8, InvalidPolicy::Bytes),
+            "a?b??"
+        );
+        assert_eq!(
+            decode_split(bytes, Encoding::Utf8, InvalidPolicy::Skip),
+            "ab"
+        );
+        assert_eq!(
+            decode_bytes(bytes, Encoding::Utf8, InvalidPolicy::Bytes).unwrap(),
+            ("ab".to_string(), 3)
+        );
+        let error = decode_bytes(bytes, Encoding::Utf8, InvalidPolicy::Error).unwrap_err();
+        assert_eq!(error.to_string(), "invalid UTF-8 at byte 1");
+
+        // Unpaired surrogates and a dangling odd byte
+        let utf16 = [0x61, 0x00, 0x00, 0xDC, 0x62, 0x00, 0x00, 0xD8, 0x63];
+        assert_eq!(
+            decode_split(&utf16, Encoding::Utf16Le, InvalidPolicy::Bytes),
+            "a??b???"
+        );
+    }
+}
@@ -0,0 +1,127 @@
+use crate::dataset::Sample;
+use crate::params::ParameterBundle;
+use crate::Method;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Accuracy metrics for a set of estimates against actual token counts
+#[derive(Debug, Clone, Serialize)]
+pub struct Metrics {
+    pub samples: usize,
+    pub rmse: f64,
+    /// Mean absolute percentage error, as a fraction
+    pub mape: f64,
+    /// Fraction of samples where the estimate is below the actual count
+    pub underestimate_rate: f64,
+    /// Percentiles of the absolute relative error
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+}
+
+/// Evaluation of one method, overall and broken down by category and language
+#[derive(Debug, Clone, Serialize)]
+pub struct EvalReport {
+    pub method: String,
+    pub overall: Metrics,
+    pub by_category: BTreeMap<String, Metrics>,
+    pub by_language: BTreeMap<String, Metrics>,
+}
+
+pub fn evaluate(method: Method, bundle: &ParameterBundle, samples: &[Sample]) -> EvalReport {
+    let estimator = bundle.estimator(method);
+
+    let mut overall = Vec::with_capacity(samples.len());
+    let mut by_category: BTreeMap<String, Vec<(f64, f64)>> = BTreeMap::new();
+    let mut by_language: BTreeMap<String, Vec<(f64, f64)>> = BTreeMap::new();
+
+    for sample in samples {
+        let pair = (estimator(&sample.text) as f64, sample.token_len as f64);
+        overall.push(pair);
+        let category = sample.category.as_deref().unwrap_or("unknown");
+        by_category
+            .entry(category.to_string())
+            .or_default()
+            .push(pair);
+        by_language
+            .entry(sample_language(&sample.text))
+            .or_default()
+            .push(pair);
+    }
+
+    EvalReport {
+        method: method.name().to_string(),
+        overall: Metrics::from_pairs(&overall),
+        by_category: by_category
+            .into_iter()
+            .map(|(key, pairs)| (key, Metrics::from_pairs(&pairs)))
+            .collect(),
+        by_language: by_language
+            .into_iter()
+            .map(|(key, pairs)| (key, Metrics::from_pairs(&pairs)))
+            .collect(),
+    }
+}
+
+/// Language of a sample, for the per-language breakdown
+#[cfg(feature = "multilingual")]
+fn sample_language(text: &str) -> String {
+    crate::detect_language(text)
+}
+
+/// Without language detection every sample is "unknown"
+#[cfg(not(feature = "multilingual"))]
+fn sample_language(_text: &str) -> String {
+    "unknown".to_string()
+}
+
+impl Metrics {
+    /// Compute metrics from `(estimate, actual)` pairs
+    pub fn from_pairs(pairs: &[(f64, f64)]) -> Self {
+        let n = pairs.len().max(1) as f64;
+
+        let squared: f64 = pairs.iter().map(|(est, act)| (est - act).powi(2)).sum();
+        // Zero-token samples would divide by zero; treat them as one token
+        let mut relative: Vec<f64> = pairs
+            .iter()
+            .map(|(est, act)| (est - act).abs() / act.max(1.0))
+            .collect();
+        relative.sort_by(f64::total_cmp);
+        let under = pairs.iter().filter(|(est, act)| est < act).count();
+
+        Self {
+            samples: pairs.len(),
+            rmse: (squared / n).sqrt(),
+            mape: relative.iter().sum::<f64>() / n,
+            underestimate_rate: under as f64 / n,
+            p50: percentile(&relative, 0.50),
+            p90: percentile(&relative, 0.90),
+            p99: percentile(&relative, 0.99),
+        }
+    }
+}
+
+// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[f64], q: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (q * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics_from_pairs() {
+        let metrics = Metrics::from_pairs(&[(9.0, 10.0), (10.0, 10.0), (12.0, 10.0), (10.0, 10.0)]);
+        assert_eq!(metrics.samples, 4);
+        assert!((metrics.rmse - (5.0f64 / 4.0).sqrt()).abs() < 1e-9);
+        assert!((metrics.mape - 0.075).abs() < 1e-9);
+        assert_eq!(metrics.underestimate_rate, 0.25);
+        assert_eq!(metrics.p50, 0.0);
+        assert_eq!(metrics.p99, 0.2);
+    }
+}
@@ -0,0 +1,804 @@
+use crate::dataset::{Sample, ToolSample};
+use crate::methods::method::EstimationMethod;
+use crate::methods::method_basic::{BasicFeatures, BasicMethod, BasicParameters};
+#[cfg(feature = "multilingual")]
+use crate::methods::method_code::uses_code_path;
+use crate::methods::method_code::{is_code, CodeMethod, CodeParameters};
+use crate::methods::method_json::{JsonMethod, JsonParameters};
+#[cfg(feature = "multilingual")]
+use crate::methods::method_multilingual::{
+    MultilingualMethod, MultilingualMethodParameters, MultilingualParameters,
+};
+#[cfg(feature = "multilingual")]
+use crate::methods::method_multilingual_simple::{
+    MultilingualSimpleMethod, MultilingualSimpleMethodParameters, MultilingualSimpleParameters,
+};
+use crate::methods::method_simple::{SimpleMethod, SimpleParameters};
+use crate::params::ParameterBundle;
+use crate::tools::{ToolEstimator, ToolParameters};
+use crate::Method;
+#[cfg(feature = "multilingual")]
+use std::collections::HashMap;
+
+/// Loss minimized when fitting coefficients
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Loss {
+    /// Ordinary least squares
+    #[default]
+    Squared,
+    /// Squared error with underestimates weighted `under_penalty` times more
+    /// than overestimates
+    Asymmetric { under_penalty: f64 },
+    /// Pinball loss on the error relative to the estimate at quantile `q`; `q = 0.95` aims
+    /// for about 5% of samples being underestimated
+    Quantile(f64),
+}
+
+/// Options controlling how coefficients are fitted to a dataset
+#[derive(Debug, Clone)]
+pub struct FitOptions {
+    /// Constrain feature coefficients to be non-negative (intercepts stay free)
+    pub non_negative: bool,
+    /// Fit separate coefficients per detected language for multilingual methods
+    pub per_language: bool,
+    pub loss: Loss,
+    /// Languages with fewer samples fall back to `default_params`
+    pub min_samples: usize,
+    /// How strongly per-language coefficients are pulled toward `default_params`,
+    /// in pseudo-samples; 0 fits each language independently
+    pub shrinkage: f64,
+}
+
+impl Default for FitOptions {
+    fn default() -> Self {
+        Self {
+            non_negative: false,
+            per_language: true,
+            loss: Loss::Squared,
+            min_samples: 10,
+            shrinkage: 10.0,
+        }
+    }
+}
+
+impl FitOptions {
+    /// Check that the loss parameters are in range: an under penalty must be
+    /// finite and at least 1, a quantile strictly between 0 and 1
+    pub fn validate(&self) -> Result<(), String> {
+        match self.loss {
+            Loss::Asymmetric { under_penalty }
+                if !(under_penalty.is_finite() && under_penalty >= 1.0) =>
+            {
+                Err(format!(
+                    "under penalty must be a finite number of at least 1, got {under_penalty}"
+                ))
+            }
+            Loss::Quantile(q) if !(q > 0.0 && q < 1.0) => {
+                Err(format!("quantile must be between 0 and 1, got {q}"))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Fit the parameters of `method` and store them in the matching bundle
+/// section. Returns `false`, leaving the section unchanged, when there are
+/// fewer than `min_samples` samples the method applies to. Fails when
+/// `options` does not validate
+pub fn fit(
+    method: Method,
+    samples: &[Sample],
+    options: &FitOptions,
+    bundle: &mut ParameterBundle,
+) -> Result<bool, String> {
+    options.validate()?;
+    match method {
+        Method::Simple => match fit_simple(samples, options) {
+            Some(params) => bundle.simple = params,
+            None => return Ok(false),
+        },
+        Method::Basic => match fit_basic(samples, options) {
+            Some(params) => bundle.basic = params,
+            None => return Ok(false),
+        },
+        #[cfg(feature = "multilingual")]
+        Method::Multilingual => match fit_multilingual(samples, options) {
+            Some(params) => bundle.multilingual = params,
+            None => return Ok(false),
+        },
+        #[cfg(feature = "multilingual")]
+        Method::MultilingualSimple => match fit_multilingual_simple(samples, options) {
+            Some(params) => bundle.multilingual_simple = params,
+            None => return Ok(false),
+        },
+        Method::Json => match fit_json(samples, bundle, options) {
+            Some(params) => bundle.json = params,
+            None => return Ok(false),
+        },
+        Method::Code => match fit_code(samples, options) {
+            Some(params) => bundle.code = params,
+            None => return Ok(false),
+        },
+    }
+    Ok(true)
+}
+
+/// Fit the simple method. Returns `None` when there are fewer than
+/// `min_samples` samples
+pub fn fit_simple(samples: &[Sample], options: &FitOptions) -> Option<SimpleParameters> {
+    let estimator = SimpleMethod::new();
+    let mut system = Regression::new(1);
+    for sample in samples {
+        let char_count = estimator.count(&sample.text);
+        system.add(&[char_count as f64], sample.token_len as f64);
+    }
+
+    if system.len() < options.min_samples.max(1) {
+        return None;
+    }
+
+    let coef = system.solve(&[options.non_negative], options.loss, None);
+    Some(SimpleParameters {
+        coefficient: coef[0] as f32,
+    })
+}
+
+/// Fit the basic method. Returns `None` when there are fewer than
+/// `min_samples` samples
+pub fn fit_basic(samples: &[Sample], options: &FitOptions) -> Option<BasicParameters> {
+    let estimator = BasicMethod::new();
+    let mut system = Regression::new(BASIC_FEATURES);
+    for sample in samples {
+        let features = estimator.count(&sample.text);
+        system.add(&basic_row(&features), sample.token_len as f64);
+    }
+
+    if system.len() < options.min_samples.max(1) {
+        return None;
+    }
+
+    let coef = system.solve(&basic_constraints(options), options.loss, None);
+    Some(BasicParameters {
+        char_coef: coef[0] as f32,
+        word_coef: coef[1] as f32,
+        avg_word_length_coef: coef[2] as f32,
+        space_coef: coef[3] as f32,
+        intercept: coef[4] as f32,
+    })
+}
+
+/// Fit the multilingual method on the samples that are not code. Returns
+/// `None` when fewer than `min_samples` remain
+#[cfg(feature = "multilingual")]
+pub fn fit_multilingual(
+    samples: &[Sample],
+    options: &FitOptions,
+) -> Option<MultilingualMethodParameters> {
+    let estimator = MultilingualMethod::new();
+    let mut global = Regression::new(BASIC_FEATURES);
+    let mut languages: HashMap<String, Regression> = HashMap::new();
+
+    // Code is estimated by the code method, so it is left out here
+    for sample in samples.iter().filter(|s| !uses_code_path(&s.text)) {
+        let features = estimator.count(&sample.text);
+        let row = basic_row(&features.basic_features);
+        let target = sample.token_len as f64;
+        global.add(&row, target);
+        if options.per_language && features.language != "unknown" {
+            languages
+                .entry(features.language)
+                .or_insert_with(|| Regression::new(BASIC_FEATURES))
+                .add(&row, target);
+        }
+    }
+
+    let to_params = |coef: &[f64]| MultilingualParameters {
+        char_coef: coef[0] as f32,
+        word_coef: coef[1] as f32,
+        avg_word_length_coef: coef[2] as f32,
+        space_coef: coef[3] as f32,
+        intercept: coef[4] as f32,
+    };
+
+    let fitted = fit_grouped(&global, languages, &basic_constraints(options), options)?;
+    Some(MultilingualMethodParameters {
+        default_params: to_params(&fitted.default_coef),
+        language_params: fitted
+            .languages
+            .iter()
+            .map(|(lang, coef)| (lang.clone(), to_params(coef)))
+            .collect(),
+        language_samples: fitted.language_samples,
+    })
+}
+
+/// Fit the multilingual simple method on the samples that are not code.
+/// Returns `None` when fewer than `min_samples` remain
+#[cfg(feature = "multilingual")]
+pub fn fit_multilingual_simple(
+    samples: &[Sample],
+    options: &FitOptions,
+) -> Option<MultilingualSimpleMethodParameters> {
+    let estimator = MultilingualSimpleMethod::new();
+    let mut global = Regression::new(1);
+    let mut languages: HashMap<String, Regression> = HashMap::new();
+
+    for sample in samples.iter().filter(|s| !uses_code_path(&s.text)) {
+        let features = estimator.count(&sample.text);
+        let row = [features.char_count as f64];
+        let target = sample.token_len as f64;
+        global.add(&row, target);
+        if options.per_language && features.language != "unknown" {
+            languages
+                .entry(features.language)
+                .or_insert_with(|| Regression::new(1))
+                .add(&row, target);
+        }
+    }
+
+    let to_params = |coef: &[f64]| MultilingualSimpleParameters {
+        coefficient: coef[0] as f32,
+    };
+
+    let fitted = fit_grouped(&global, languages, &[options.non_negative], options)?;
+    Some(MultilingualSimpleMethodParameters {
+        default_params: to_params(&fitted.default_coef),
+        language_params: fitted
+            .languages
+            .iter()
+            .map(|(lang, coef)| (lang.clone(), to_params(coef)))
+            .collect(),
+        language_samples: fitted.language_samples,
+    })
+}
+
+#[cfg(feature = "multilingual")]
+struct GroupedFit {
+    default_coef: Vec<f64>,
+    languages: Vec<(String, Vec<f64>)>,
+    language_samples: HashMap<String, usize>,
+}
+
+// Fit the shared coefficients, then each language with enough samples,
+// shrunk toward the shared ones so sparse languages cannot overfit. `None`
+// when there are too few samples for the shared coefficients
+#[cfg(feature = "multilingual")]
+fn fit_grouped(
+    global: &Regression,
+    languages: HashMap<String, Regression>,
+    constraints: &[bool],
+    options: &FitOptions,
+) -> Option<GroupedFit> {
+    if global.len() < options.min_samples.max(1) {
+        return None;
+    }
+    let default_coef = global.solve(constraints, options.loss, None);
+    let prior = Prior {
+        coef: &default_coef,
+        strength: options.shrinkage,
+    };
+
+    let mut fitted = Vec::new();
+    let mut language_samples = HashMap::new();
+    for (lang, system) in languages {
+        if system.len() < options.min_samples.max(1) {
+            continue;
+        }
+        let prior = (options.shrinkage > 0.0).then_some(&prior);
+        fitted.push((lang.clone(), system.solve(constraints, options.loss, prior)));
+        language_samples.insert(lang, system.len());
+    }
+
+    Some(GroupedFit {
+        default_coef,
+        languages: fitted,
+        language_samples,
+    })
+}
+
+/// Fit the JSON method on the samples whose text parses as JSON, estimating
+/// keys and values with the bundle's multilingual simple parameters. Returns
+/// `None` when fewer than `min_samples` are JSON
+pub fn fit_json(
+    samples: &[Sample],
+    bundle: &ParameterBundle,
+    options: &FitOptions,
+) -> Option<JsonParameters> {
+    let mut estimator = JsonMethod::new();
+    estimator.set_text_parameters(bundle.text_parameters());
+    let mut system = Regression::new(9);
+    for sample in samples {
+        if serde_json::from_str::<serde::de::IgnoredAny>(&sample.text).is_err() {
+            continue;
+        }
+        let features = estimator.count(&sample.text);
+        let row = [
+            features.punctuation_count as f64,
+            features.string_count as f64,
+            features.key_tokens as f64,
+            features.value_tokens as f64,
+            features.number_chars as f64,
+            features.literal_count as f64,
+            features.newline_count as f64,
+            features.other_chars as f64,
+            1.0,
+        ];
+        system.add(&row, sample.token_len as f64);
+    }
+    if system.len() < options.min_samples.max(1) {
+        return None;
+    }
+
+    let nn = options.non_negative;
+    let coef = system.solve(&[nn, nn, nn, nn, nn, nn, nn, nn, false], options.loss, None);
+    Some(JsonParameters {
+        punctuation_coef: coef[0] as f32,
+        string_coef: coef[1] as f32,
+        key_coef: coef[2] as f32,
+        value_coef: coef[3] as f32,
+        number_coef: coef[4] as f32,
+        literal_coef: coef[5] as f32,
+        newline_coef: coef[6] as f32,
+        other_coef: coef[7] as f32,
+        intercept: coef[8] as f32,
+    })
+}
+
+/// Fit the code method on the samples detected as source code. Returns `None`
+/// when fewer than `min_samples` are code
+pub fn fit_code(samples: &[Sample], options: &FitOptions) -> Option<CodeParameters> {
+    let estimator = CodeMethod::new();
+    let mut system = Regression::new(9);
+    for sample in samples.iter().filter(|s| is_code(&s.text)) {
+        let features = estimator.count(&sample.text);
+        let row = [
+            features.identifier_parts as f64,
+            features.identifier_chars as f64,
+            features.number_chars as f64,
+            features.operator_runs as f64,
+            features.operator_chars as f64,
+            features.indent_runs as f64,
+            features.newline_count as f64,
+            features.other_chars as f64,
+            1.0,
+        ];
+        system.add(&row, sample.token_len as f64);
+    }
+    if system.len() < options.min_samples.max(1) {
+        return None;
+    }
+
+    let nn = options.non_negative;
+    let coef = system.solve(&[nn, nn, nn, nn, nn, nn, nn, nn, false], options.loss, None);
+    Some(CodeParameters {
+        part_coef: coef[0] as f32,
+        identifier_char_coef: coef[1] as f32,
+        number_coef: coef[2] as f32,
+        operator_run_coef: coef[3] as f32,
+        operator_char_coef: coef[4] as f32,
+        indent_coef: coef[5] as f32,
+        newline_coef: coef[6] as f32,
+        other_coef: coef[7] as f32,
+        intercept: coef[8] as f32,
+    })
+}
+
+/// Fit tool definition calibration, using `method` for the rendered schema
+/// text; `None` when there are fewer than `min_samples` samples
+pub fn fit_tools(
+    samples: &[ToolSample],
+    method: Method,
+    bundle: &ParameterBundle,
+    options: &FitOptions,
+) -> Result<Option<ToolParameters>, String> {
+    options.validate()?;
+    if samples.len() < options.min_samples.max(1) {
+        return Ok(None);
+    }
+
+    let estimator = ToolEstimator::from_bundle(bundle, method);
+    let mut system = Regression::new(5);
+    for sample in samples {
+        let features = estimator.count(&sample.tools);
+        let row = [
+            features.text_tokens as f64,
+            features.tool_count as f64,
+            features.property_count as f64,
+            features.enum_value_count as f64,
+            1.0,
+        ];
+        system.add(&row, sample.token_len as f64);
+    }
+
+    let nn = options.non_negative;
+    let coef = system.solve(&[nn, nn, nn, nn, false], options.loss, None);
+    Ok(Some(ToolParameters {
+        text_coef: coef[0] as f32,
+        per_tool: coef[1] as f32,
+        per_property: coef[2] as f32,
+        per_enum_value: coef[3] as f32,
+        intercept: coef[4] as f32,
+    }))
+}
+
+// Feature rows for the basic and multilingual methods: the four counts plus
+// a constant column for the intercept
+const BASIC_FEATURES: usize = 5;
+
+fn basic_row(features: &BasicFeatures) -> [f64; BASIC_FEATURES] {
+    [
+        features.char_count as f64,
+        features.word_count as f64,
+        features.avg_word_length as f64,
+        features.space_count as f64,
+        1.0,
+    ]
+}
+
+fn basic_constraints(options: &FitOptions) -> [bool; BASIC_FEATURES] {
+    let nn = options.non_negative;
+    [nn, nn, nn, nn, false]
+}
+
+/// Rows and targets of a small linear regression problem
+struct Regression {
+    n: usize,
+    rows: Vec<f64>,
+    targets: Vec<f64>,
+}
+
+impl Regression {
+    fn new(n: usize) -> Self {
+        Self {
+            n,
+            rows: Vec::new(),
+            targets: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, row: &[f64], target: f64) {
+        self.rows.extend_from_slice(&row[..self.n]);
+        self.targets.push(target);
+    }
+
+    fn len(&self) -> usize {
+        self.targets.len()
+    }
+
+    fn solve(&self, non_negative: &[bool], loss: Loss, prior: Option<&Prior>) -> Vec<f64> {
+        let mut coef = self.normal_equations(|_, _| 1.0, prior).solve(non_negative);
+        if loss == Loss::Squared {
+            return coef;
+        }
+
+        // Iteratively reweighted least squares; the residual is `actual - estimate`,
+        // so a positive residual is an underestimate
+        let weight = |residual: f64, estimate: f64| match loss {
+            Loss::Squared => 1.0,
+            Loss::Asymmetric { under_penalty } if residual > 0.0 => under_penalty,
+            Loss::Asymmetric { .. } => 1.0,
+            Loss::Quantile(q) => {
+                // Relative error keeps short texts from being outvoted by long ones,
+                // so the quantile bounds the share of underestimated samples
+                let side = if residual > 0.0 { q } else { 1.0 - q };
+                side / (residual.abs().max(1e-3) * estimate.max(1.0))
+            }
+        };
+        for _ in 0..100 {
+            let next = self
+                .normal_equations(
+                    |row, target| {
+                        let estimate = dot(row, &coef);
+                        weight(target - estimate, estimate)
+                    },
+                    prior,
+                )
+                .solve(non_negative);
+            let step = next
+                .iter()
+                .zip(&coef)
+                .map(|(a, b)| (a - b).abs())
+                .fold(0.0, f64::max);
+            coef = next;
+            if step < 1e-9 {
+                break;
+            }
+        }
+        coef
+    }
+
+    fn normal_equations(
+        &self,
+        weight: impl Fn(&[f64], f64) -> f64,
+        prior: Option<&Prior>,
+    ) -> LeastSquares {
+        let mut system = LeastSquares::new(self.n);
+        let mut total_weight = 0.0;
+        for (row, target) in self.rows.chunks(self.n).zip(&self.targets) {
+            let w = weight(row, *target);
+            system.add(row, *target, w);
+            total_weight += w;
+        }
+
+        // Ridge penalty toward the prior, scaled per feature so that `strength`
+        // acts like that many average samples agreeing with the prior
+        if let (Some(prior), true) = (prior, total_weight > 0.0) {
+            let n = self.n;
+            for j in 0..n {
+                let penalty = prior.strength * system.xtx[j * n + j] / total_weight;
+                system.xtx[j * n + j] += penalty;
+                system.xty[j] += penalty * prior.coef[j];
+            }
+        }
+        system
+    }
+}
+
+/// Coefficients to shrink a fit toward, worth `strength` pseudo-samples
+struct Prior<'a> {
+    coef: &'a [f64],
+    strength: f64,
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Accumulated normal equations `XᵀWX β = XᵀWy` for a small linear model
+struct LeastSquares {
+    n: usize,
+    xtx: Vec<f64>,
+    xty: Vec<f64>,
+}
+
+impl LeastSquares {
+    fn new(n: usize) -> Self {
+        Self {
+            n,
+            xtx: vec![0.0; n * n],
+            xty: vec![0.0; n],
+        }
+    }
+
+    fn add(&mut self, row: &[f64], target: f64, weight: f64) {
+        for i in 0..self.n {
+            self.xty[i] += weight * row[i] * target;
+            for j in 0..self.n {
+                self.xtx[i * self.n + j] += weight * row[i] * row[j];
+            }
+        }
+    }
+
+    /// Solve for the coefficients; `non_negative[i]` clamps coefficient `i` at zero
+    fn solve(&self, non_negative: &[bool]) -> Vec<f64> {
+        let n = self.n;
+        // A tiny ridge keeps collinear or under-determined systems solvable
+        let trace: f64 = (0..n).map(|i| self.xtx[i * n + i]).sum();
+        let ridge = 1e-9 * (trace / n as f64).max(1e-12);
+        let mut a = self.xtx.clone();
+        for i in 0..n {
+            a[i * n + i] += ridge;
+        }
+
+        let mut coef = gaussian_elimination(a.clone(), self.xty.clone());
+        if !non_negative.iter().any(|&nn| nn) {
+            return coef;
+        }
+
+        // Projected coordinate descent on the quadratic objective
+        for (c, &nn) in coef.iter_mut().zip(non_negative) {
+            if nn && *c < 0.0 {
+                *c = 0.0;
+            }
+        }
+        for _ in 0..10_000 {
+            let mut max_step: f64 = 0.0;
+            for i in 0..n {
+                let residual: f64 = self.xty[i]
+                    - (0..n)
+                        .filter(|&j| j != i)
+                        .map(|j| a[i * n + j] * coef[j])
+                        .sum::<f64>();
+                let mut value = residual / a[i * n + i];
+                if non_negative[i] {
+                    value = value.max(0.0);
+                }
+                max_step = max_step.max((value - coef[i]).abs());
+                coef[i] = value;
+            }
+            if max_step < 1e-10 {
+                break;
+            }
+        }
+        coef
+    }
+}
+
+fn gaussian_elimination(mut a: Vec<f64>, mut b: Vec<f64>) -> Vec<f64> {
+    let n = b.len();
+    for col in 0..n {
+        let pivot = (col..n)
+            .max_by(|&x, &y| a[x * n + col].abs().total_cmp(&a[y * n + col].abs()))
+            .unwrap();
+        if a[pivot * n + col] == 0.0 {
+            continue;
+        }
+        if pivot != col {
+            for k in 0..n {
+                a.swap(pivot * n + k, col * n + k);
+            }
+            b.swap(pivot, col);
+        }
+        for row in col + 1..n {
+            let factor = a[row * n + col] / a[col * n + col];
+            for k in col..n {
+                a[row * n + k] -= factor * a[col * n + k];
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let diag = a[row * n + row];
+        if diag == 0.0 {
+            continue;
+        }
+        let sum: f64 = (row + 1..n).map(|k| a[row * n + k] * x[k]).sum();
+        x[row] = (b[row] - sum) / diag;
+    }
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(text: &str, token_len: usize) -> Sample {
+        Sample {
+            text: text.to_string(),
+            token_len,
+            category: None,
+        }
+    }
+
+    #[test]
+    fn test_fit_simple_recovers_ratio() {
+        let samples = vec![
+            sample("abcd", 1),
+            sample("abcdefgh", 2),
+            sample("abcdefghijkl", 3),
+        ];
+        let options = FitOptions {
+            min_samples: 3,
+            ..FitOptions::default()
+        };
+        let params = fit_simple(&samples, &options).unwrap();
+        assert!((params.coefficient - 0.25).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_fit_needs_min_samples() {
+        let samples = vec![sample("abcd", 1), sample("abcdefgh", 2)];
+        let options = FitOptions {
+            min_samples: 3,
+            ..FitOptions::default()
+        };
+        let mut bundle = ParameterBundle::default();
+        assert!(!fit(Method::Simple, &samples, &options, &mut bundle).unwrap());
+        assert!(!fit(Method::Basic, &samples, &options, &mut bundle).unwrap());
+
+        // Code is left out of the multilingual fits, leaving no samples
+        #[cfg(feature = "multilingual")]
+        {
+            let code = "```rust\nfn main() {}\n```\n";
+            let samples = vec![sample(code, 12); 3];
+            assert!(!fit(Method::Multilingual, &samples, &options, &mut bundle).unwrap());
+            assert!(!fit(Method::MultilingualSimple, &samples, &options, &mut bundle).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_fit_tools_needs_min_samples() {
+        let tools = crate::tools::parse_tools(
+            r#"[{"type": "function", "function": {"name": "get_weather", "parameters": {}}}]"#,
+        )
+        .unwrap();
+        let samples: Vec<ToolSample> = (0..3)
+            .map(|_| ToolSample {
+                tools: tools.clone(),
+                token_len: 20,
+            })
+            .collect();
+        let bundle = ParameterBundle::default();
+        let options = FitOptions::default();
+        assert!(fit_tools(&samples, Method::Simple, &bundle, &options)
+            .unwrap()
+            .is_none());
+        let options = FitOptions {
+            min_samples: 3,
+            ..options
+        };
+        assert!(fit_tools(&samples, Method::Simple, &bundle, &options)
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn test_non_negative_clamps_coefficients() {
+        let mut system = Regression::new(2);
+        // y = 2a - b exactly; constraining b pushes it to zero
+        for (a, b) in [(1.0, 1.0), (2.0, 1.0), (3.0, 2.0), (4.0, 5.0)] {
+            system.add(&[a, b], 2.0 * a - b);
+        }
+        let free = system.solve(&[false, false], Loss::Squared, None);
+        assert!((free[0] - 2.0).abs() < 1e-6 && (free[1] + 1.0).abs() < 1e-6);
+        let constrained = system.solve(&[false, true], Loss::Squared, None);
+        assert_eq!(constrained[1], 0.0);
+    }
+
+    #[test]
+    fn test_fit_rejects_invalid_loss() {
+        let samples = vec![sample("abcd", 1), sample("abcdefgh", 2)];
+        let mut bundle = ParameterBundle::default();
+        for loss in [
+            Loss::Asymmetric { under_penalty: 0.0 },
+            Loss::Asymmetric {
+                under_penalty: f64::NAN,
+            },
+            Loss::Quantile(1.0),
+        ] {
+            let options = FitOptions {
+                loss,
+                min_samples: 1,
+                ..FitOptions::default()
+            };
+            assert!(fit(Method::Simple, &samples, &options, &mut bundle).is_err());
+        }
+        assert_eq!(
+            bundle.simple.coefficient,
+            ParameterBundle::default().simple.coefficient
+        );
+    }
+
+    #[test]
+    fn test_quantile_loss_bounds_underestimates() {
+        let mut system = Regression::new(1);
+        // Ratios of 0.1, 0.2, ..., 1.0 tokens per char
+        for i in 1..=10 {
+            system.add(&[100.0], 10.0 * i as f64);
+        }
+        let squared = system.solve(&[false], Loss::Squared, None);
+        assert!((squared[0] - 0.55).abs() < 1e-6);
+        let safe = system.solve(&[false], Loss::Quantile(0.9), None);
+        assert!(safe[0] >= 0.89 && safe[0] <= 1.0);
+        let penalized = system.solve(&[false], Loss::Asymmetric { under_penalty: 4.0 }, None);
+        assert!(penalized[0] > squared[0]);
+    }
+
+    #[test]
+    #[cfg(feature = "multilingual")]
+    fn test_sparse_languages_fall_back_and_shrink() {
+        let mut global = Regression::new(1);
+        let mut languages = HashMap::new();
+        for (lang, ratio, count) in [("eng", 0.25, 40), ("jpn", 0.75, 4), ("fra", 0.5, 20)] {
+            let system = languages
+                .entry(lang.to_string())
+                .or_insert_with(|| Regression::new(1));
+            for _ in 0..count {
+                system.add(&[100.0], 100.0 * ratio);
+                global.add(&[100.0], 100.0 * ratio);
+            }
+        }
+
+        let options = FitOptions::default();
+        let fitted = fit_grouped(&global, languages, &[false], &options).unwrap();
+        assert!(!fitted.language_samples.contains_key("jpn"));
+        assert_eq!(fitted.language_samples["fra"], 20);
+
+        // Ten pseudo-samples at the shared ratio pull fra toward it
+        let (_, fra) = fitted.languages.iter().find(|(l, _)| l == "fra").unwrap();
+        let expected = (20.0 * 0.5 + 10.0 * fitted.default_coef[0]) / 30.0;
+        assert!((fra[0] - expected).abs() < 1e-6);
+    }
+}
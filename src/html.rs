@@ -0,0 +1,320 @@
+use crate::methods::method::EstimationMethod;
+use crate::methods::method_code::CodeMethod;
+use crate::params::ParameterBundle;
+use crate::{Method, DEFAULT_METHOD};
+use serde::Serialize;
+
+/// Estimates of an HTML or XML document, raw and stripped
+#[derive(Debug, Clone, Serialize)]
+pub struct HtmlEstimate {
+    /// The document as sent: `markup_tokens + text_tokens`
+    pub raw_tokens: usize,
+    /// Tags, comments, scripts, styles and indentation
+    pub markup_tokens: usize,
+    /// Text nodes as they appear in the document, entities undecoded
+    pub text_tokens: usize,
+    /// Visible text only, as produced by `strip_html`
+    pub stripped_tokens: usize,
+}
+
+impl HtmlEstimate {
+    /// Share of the raw estimate spent on markup
+    pub fn markup_share(&self) -> f64 {
+        self.markup_tokens as f64 / self.raw_tokens.max(1) as f64
+    }
+}
+
+enum Segment<'a> {
+    /// Tags, comments, declarations and processing instructions
+    Markup(&'a str),
+    /// Contents of `<script>` and `<style>`, never visible
+    Hidden(&'a str),
+    Text(&'a str),
+}
+
+fn segments(html: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut rest = html;
+    while !rest.is_empty() {
+        let Some(open) = rest.find('<') else {
+            segments.push(Segment::Text(rest));
+            break;
+        };
+        if open > 0 {
+            segments.push(Segment::Text(&rest[..open]));
+            rest = &rest[open..];
+        }
+
+        // CDATA contents are text, wrapped in markup
+        if let Some(body) = rest.strip_prefix("<![CDATA[") {
+            let close = body.find("]]>");
+            let content = &body[..close.unwrap_or(body.len())];
+            segments.push(Segment::Markup("<![CDATA["));
+            if !content.is_empty() {
+                segments.push(Segment::Text(content));
+            }
+            rest = match close {
+                Some(close) => {
+                    segments.push(Segment::Markup("]]>"));
+                    &body[close + 3..]
+                }
+                None => "",
+            };
+            continue;
+        }
+
+        let end = if rest.starts_with("<!--") {
+            rest.find("-->").map_or(rest.len(), |i| i + 3)
+        } else {
+            rest.find('>').map_or(rest.len(), |i| i + 1)
+        };
+        let tag = &rest[..end];
+        segments.push(Segment::Markup(tag));
+        rest = &rest[end..];
+
+        // Script and style bodies run to their closing tag
+        if let Some(name) = ["script", "style"]
+            .into_iter()
+            .find(|name| opens(tag, name))
+        {
+            let close = find_ignore_case(rest, &format!("</{name}")).unwrap_or(rest.len());
+            if close > 0 {
+                segments.push(Segment::Hidden(&rest[..close]));
+            }
+            rest = &rest[close..];
+        }
+    }
+    segments
+}
+
+fn opens(tag: &str, name: &str) -> bool {
+    let Some(after) = tag.get(1..1 + name.len()) else {
+        return false;
+    };
+    after.eq_ignore_ascii_case(name)
+        && tag[1 + name.len()..].starts_with(|c: char| c == '>' || c.is_whitespace())
+        && !tag.ends_with("/>")
+}
+
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+const BLOCK_TAGS: &[&str] = &[
+    "p",
+    "div",
+    "br",
+    "li",
+    "tr",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "section",
+    "article",
+    "header",
+    "footer",
+    "table",
+    "ul",
+    "ol",
+    "pre",
+    "blockquote",
+    "title",
+];
+
+/// Elements whose text sits apart from its neighbours on the same line
+const CELL_TAGS: &[&str] = &["td", "th", "dt", "dd"];
+
+/// What separates text on either side of `tag`: a line break for block
+/// elements, a space for table cells, nothing for inline elements
+fn tag_separator(tag: &str) -> Option<char> {
+    let name: String = tag
+        .trim_start_matches(['<', '/'])
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect();
+    let is = |tags: &[&str]| tags.iter().any(|t| t.eq_ignore_ascii_case(&name));
+    if is(BLOCK_TAGS) {
+        Some('\n')
+    } else if is(CELL_TAGS) {
+        Some(' ')
+    } else {
+        None
+    }
+}
+
+/// Visible text of an HTML or XML document: tags, comments, scripts and styles
+/// removed, common entities decoded, whitespace collapsed, block elements put
+/// on their own lines and table cells separated by spaces
+pub fn strip_html(html: &str) -> String {
+    let mut text = String::new();
+    for segment in segments(html) {
+        match segment {
+            Segment::Markup(tag) => text.extend(tag_separator(tag)),
+            Segment::Hidden(_) => {}
+            Segment::Text(raw) => text.push_str(&decode_entities(raw)),
+        }
+    }
+
+    let lines: Vec<String> = text
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect();
+    lines.join("\n")
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest.find(';').filter(|end| *end <= 10).and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .map(|hex| u32::from_str_radix(hex, 16))
+                    .or_else(|| entity.strip_prefix('#').map(str::parse))
+                    .and_then(Result::ok)
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, end + 1))
+        });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Markup and raw text of a document. Whitespace between tags is indentation,
+/// which the code method counts, so it goes with the markup. Text nodes on
+/// either side of a block or cell boundary are kept apart
+fn markup_and_text(html: &str) -> (String, String) {
+    let mut markup = String::new();
+    let mut text = String::new();
+    let mut separator = None;
+    for segment in segments(html) {
+        match segment {
+            Segment::Text(raw) if !raw.trim().is_empty() => {
+                if !text.is_empty() {
+                    text.extend(separator);
+                }
+                separator = None;
+                text.push_str(raw);
+            }
+            Segment::Markup(s) | Segment::Hidden(s) | Segment::Text(s) => {
+                // A line break outranks a space
+                if let Segment::Markup(tag) = segment {
+                    if separator != Some('\n') {
+                        separator = tag_separator(tag).or(separator);
+                    }
+                }
+                markup.push_str(s);
+            }
+        }
+    }
+    (markup, text)
+}
+
+/// Estimates markup and text of HTML or XML separately: markup with the code
+/// method, text with a text method
+pub struct HtmlEstimator {
+    prose: Box<dyn Fn(&str) -> usize + Send + Sync>,
+    code: CodeMethod,
+}
+
+impl HtmlEstimator {
+    /// Estimator using the compiled-in parameters and the default text method
+    pub fn new() -> Self {
+        Self::from_bundle(&ParameterBundle::default(), DEFAULT_METHOD)
+    }
+
+    pub fn from_bundle(bundle: &ParameterBundle, method: Method) -> Self {
+        let mut code = CodeMethod::new();
+        code.set_parameters(bundle.code.clone());
+        Self {
+            prose: bundle.estimator(method),
+            code,
+        }
+    }
+
+    pub fn estimate(&self, html: &str) -> HtmlEstimate {
+        let (markup, text) = markup_and_text(html);
+        let markup_tokens = self.code.estimate(&markup);
+        let text_tokens = (self.prose)(&text);
+        HtmlEstimate {
+            raw_tokens: markup_tokens + text_tokens,
+            markup_tokens,
+            text_tokens,
+            stripped_tokens: (self.prose)(&strip_html(html)),
+        }
+    }
+}
+
+impl Default for HtmlEstimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = "<!DOCTYPE html>\n<html>\n<head>\n  <title>Fish &amp; Chips</title>\n  <style>body { color: red; }</style>\n</head>\n<body>\n  <!-- nav -->\n  <p class=\"intro\">Hello <b>world</b>&#33;</p>\n  <script>var x = \"<p>\";</script>\n</body>\n</html>";
+
+    #[test]
+    fn test_strip_html() {
+        assert_eq!(strip_html(PAGE), "Fish & Chips\nHello world!");
+        assert_eq!(strip_html("<a><![CDATA[x < y]]></a>"), "x < y");
+    }
+
+    #[test]
+    fn test_markup_and_text_are_separated() {
+        let estimate = HtmlEstimator::new().estimate(PAGE);
+        assert_eq!(
+            estimate.raw_tokens,
+            estimate.markup_tokens + estimate.text_tokens
+        );
+        assert!(estimate.markup_tokens > estimate.text_tokens);
+        assert!(estimate.stripped_tokens < estimate.raw_tokens);
+        assert!(estimate.markup_share() > 0.5);
+    }
+
+    #[test]
+    fn test_cells_and_blocks_are_separated() {
+        assert_eq!(strip_html("<td>a</td><td>b</td>"), "a b");
+        assert_eq!(strip_html("<div>a</div><div>b</div>"), "a\nb");
+        assert_eq!(strip_html("<b>a</b><i>b</i>"), "ab");
+
+        let table = "<table><tr><td>a</td><td>b</td></tr><tr><td>c</td></tr></table>";
+        assert_eq!(markup_and_text(table).1, "a b\nc");
+        assert_eq!(
+            markup_and_text("<p>Hello <b>world</b>!</p>").1,
+            "Hello world!"
+        );
+    }
+}
@@ -0,0 +1,221 @@
+use whatlang::{detect, detect_script, Lang};
+
+/// ISO 639-1 codes of the languages whatlang detects, with their ISO 639-3 codes
+const ISO_639_1: &[(&str, &str)] = &[
+    ("af", "afr"),
+    ("ak", "aka"),
+    ("am", "amh"),
+    ("ar", "ara"),
+    ("az", "aze"),
+    ("be", "bel"),
+    ("bg", "bul"),
+    ("bn", "ben"),
+    ("ca", "cat"),
+    ("cs", "ces"),
+    ("da", "dan"),
+    ("de", "deu"),
+    ("el", "ell"),
+    ("en", "eng"),
+    ("eo", "epo"),
+    ("es", "spa"),
+    ("et", "est"),
+    ("fa", "pes"),
+    ("fi", "fin"),
+    ("fr", "fra"),
+    ("gu", "guj"),
+    ("he", "heb"),
+    ("hi", "hin"),
+    ("hr", "hrv"),
+    ("hu", "hun"),
+    ("hy", "hye"),
+    ("id", "ind"),
+    ("it", "ita"),
+    ("ja", "jpn"),
+    ("jv", "jav"),
+    ("ka", "kat"),
+    ("km", "khm"),
+    ("kn", "kan"),
+    ("ko", "kor"),
+    ("la", "lat"),
+    ("lt", "lit"),
+    ("lv", "lav"),
+    ("mk", "mkd"),
+    ("ml", "mal"),
+    ("mr", "mar"),
+    ("my", "mya"),
+    ("nb", "nob"),
+    ("ne", "nep"),
+    ("nl", "nld"),
+    ("no", "nob"),
+    ("or", "ori"),
+    ("pa", "pan"),
+    ("pl", "pol"),
+    ("pt", "por"),
+    ("ro", "ron"),
+    ("ru", "rus"),
+    ("si", "sin"),
+    ("sk", "slk"),
+    ("sl", "slv"),
+    ("sn", "sna"),
+    ("sr", "srp"),
+    ("sv", "swe"),
+    ("ta", "tam"),
+    ("te", "tel"),
+    ("th", "tha"),
+    ("tk", "tuk"),
+    ("tl", "tgl"),
+    ("tr", "tur"),
+    ("uk", "ukr"),
+    ("ur", "urd"),
+    ("uz", "uzb"),
+    ("vi", "vie"),
+    ("yi", "yid"),
+    ("zh", "cmn"),
+    ("zu", "zul"),
+];
+
+/// Macrolanguage codes for the individual language whatlang reports
+const MACROLANGUAGES: &[(&str, &str)] = &[
+    ("arb", "ara"),
+    ("fas", "pes"),
+    ("nor", "nob"),
+    ("zho", "cmn"),
+];
+
+/// ISO 639-3 code, as used in `language_params`, of an ISO 639-1, ISO 639-3
+/// or BCP-47 tag such as "ja", "jpn" or "ja-JP". Only the primary subtag
+/// counts, so "zh-Hant-TW" is "cmn"
+pub fn normalize_language(tag: &str) -> Result<&'static str, String> {
+    let primary = tag
+        .trim()
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let lookup = |table: &[(&str, &'static str)]| {
+        table
+            .iter()
+            .find(|(code, _)| *code == primary)
+            .map(|(_, iso3)| *iso3)
+    };
+    let code = match primary.len() {
+        2 => lookup(ISO_639_1),
+        3 => lookup(MACROLANGUAGES)
+            .or_else(|| Lang::from_code(primary.as_str()).map(|lang| lang.code())),
+        _ => None,
+    };
+    code.ok_or_else(|| format!("unknown language: {tag}"))
+}
+
+/// Picks the language whose parameters the multilingual methods use
+pub trait LanguageDetector: Send + Sync {
+    /// ISO 639-3 code of `text`, as used in `language_params`, or `None` if
+    /// the language is unknown and the default parameters apply
+    fn detect(&self, text: &str) -> Option<&str>;
+}
+
+/// Full language detection with whatlang; the default
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WhatlangDetector;
+
+impl LanguageDetector for WhatlangDetector {
+    fn detect(&self, text: &str) -> Option<&str> {
+        detect(text).map(|info| info.lang().code())
+    }
+}
+
+/// Detects only the writing system, which is much cheaper than full detection.
+/// Scripts used by a single language (kana, Hangul, Thai, Greek, ...) resolve
+/// to it; shared scripts such as Latin, Cyrillic or Arabic are unknown
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScriptDetector;
+
+impl LanguageDetector for ScriptDetector {
+    fn detect(&self, text: &str) -> Option<&str> {
+        match detect_script(text)?.langs() {
+            [lang] => Some(lang.code()),
+            _ => None,
+        }
+    }
+}
+
+/// Language supplied by the caller, e.g. from an HTTP header or document
+/// metadata; the text is not inspected at all
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixedLanguage {
+    code: String,
+}
+
+impl FixedLanguage {
+    /// `code` is an ISO 639-3 code such as "jpn"
+    pub fn new(code: impl Into<String>) -> Self {
+        Self { code: code.into() }
+    }
+
+    /// Language of any tag `normalize_language` accepts, e.g. "ja-JP"
+    pub fn from_tag(tag: &str) -> Result<Self, String> {
+        normalize_language(tag).map(Self::new)
+    }
+
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+}
+
+impl LanguageDetector for FixedLanguage {
+    fn detect(&self, _text: &str) -> Option<&str> {
+        Some(&self.code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::methods::method::EstimationMethod;
+    use crate::methods::method_multilingual_simple::MultilingualSimpleMethod;
+
+    #[test]
+    fn test_detectors() {
+        let text = "これは日本語のテキストです。ひらがなとカタカナを含みます。";
+        assert_eq!(WhatlangDetector.detect(text), Some("jpn"));
+        assert_eq!(ScriptDetector.detect(text), Some("jpn"));
+        assert_eq!(ScriptDetector.detect("Ο γρήγορος καφέ σκύλος"), Some("ell"));
+        // Latin is shared by many languages
+        assert_eq!(ScriptDetector.detect("The quick brown fox jumps"), None);
+        assert_eq!(FixedLanguage::new("fra").detect(text), Some("fra"));
+    }
+
+    #[test]
+    fn test_normalize_language() {
+        for tag in ["ja", "jpn", "ja-JP", "JA_jp", " ja "] {
+            assert_eq!(normalize_language(tag), Ok("jpn"), "{tag}");
+        }
+        assert_eq!(normalize_language("zh-Hant-TW"), Ok("cmn"));
+        assert_eq!(normalize_language("zho"), Ok("cmn"));
+        assert_eq!(normalize_language("fa-IR"), Ok("pes"));
+        assert_eq!(normalize_language("no"), Ok("nob"));
+        assert_eq!(
+            normalize_language("english"),
+            Err("unknown language: english".to_string())
+        );
+        assert!(normalize_language("xx").is_err());
+        assert!(normalize_language("").is_err());
+        // The table only maps to codes whatlang reports
+        for (_, iso3) in ISO_639_1 {
+            assert!(Lang::from_code(*iso3).is_some(), "{iso3}");
+        }
+    }
+
+    #[test]
+    fn test_method_uses_detector() {
+        let text = "Plain English prose, estimated as if it were Japanese.";
+        let mut method = MultilingualSimpleMethod::new();
+        method.set_detector(FixedLanguage::new("jpn"));
+        assert_eq!(method.count(text).language, "jpn");
+        let coefficient = method.parameters().language_params["jpn"].coefficient;
+        assert_eq!(
+            method.estimate(text),
+            (text.chars().count() as f32 * coefficient).round() as usize
+        );
+    }
+}
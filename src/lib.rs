@@ -1,44 +1,309 @@
+// Without `std` this is the `alloc` core: the simple and basic methods with
+// their compiled-in parameters. Everything else needs `std`
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
 #[cfg(feature = "pyo3")]
 use pyo3::prelude::*;
 
-use std::path::Path;
+use alloc::format;
+use alloc::string::String;
+use core::fmt;
+use core::str::FromStr;
+#[cfg(feature = "std")]
+use std::borrow::Cow;
 
 // Import modules
 mod methods {
     pub mod method;
     pub mod method_basic;
+    #[cfg(feature = "std")]
+    pub mod method_code;
+    #[cfg(feature = "std")]
+    pub mod method_json;
+    #[cfg(feature = "multilingual")]
     pub mod method_multilingual;
+    #[cfg(feature = "multilingual")]
     pub mod method_multilingual_simple;
     pub mod method_simple;
 }
+#[cfg(feature = "capi")]
+mod capi;
+#[cfg(feature = "std")]
+mod chat;
+#[cfg(feature = "std")]
+mod chunk;
+#[cfg(feature = "std")]
+mod dataset;
+#[cfg(feature = "std")]
+mod decode;
+#[cfg(feature = "std")]
+mod eval;
+#[cfg(feature = "std")]
+mod fit;
+#[cfg(feature = "std")]
+mod html;
+#[cfg(feature = "multilingual")]
+mod language;
+#[cfg(feature = "std")]
+mod markdown;
+#[cfg(feature = "std")]
+mod params;
+#[cfg(feature = "std")]
+mod sampling;
+#[cfg(feature = "std")]
+mod streaming;
+#[cfg(feature = "std")]
+mod tools;
+#[cfg(feature = "std")]
+mod truncate;
+#[cfg(feature = "wasm")]
+mod wasm;
 
 // Re-export for convenience
-pub use methods::method::EstimationMethod;
+#[cfg(feature = "std")]
+pub use chat::{
+    parse_chat_messages, parse_chat_request, ChatEstimator, ChatMessage, ChatParameters,
+    ChatRequest, ContentPart, MessageContent,
+};
+#[cfg(feature = "std")]
+pub use chunk::{chunk_with, ChunkOptions};
+#[cfg(feature = "std")]
+pub use dataset::{load_dataset, load_tool_dataset, Sample, ToolSample};
+#[cfg(feature = "std")]
+pub use decode::{decode_bytes, sniff_bom, Decoded, Decoder, Encoding, InvalidPolicy};
+#[cfg(feature = "std")]
+pub use eval::{evaluate, EvalReport, Metrics};
+#[cfg(feature = "std")]
+pub use fit::{fit, fit_basic, fit_code, fit_json, fit_simple, fit_tools, FitOptions, Loss};
+#[cfg(feature = "multilingual")]
+pub use fit::{fit_multilingual, fit_multilingual_simple};
+#[cfg(feature = "std")]
+pub use html::{strip_html, HtmlEstimate, HtmlEstimator};
+#[cfg(feature = "multilingual")]
+pub use language::{
+    normalize_language, FixedLanguage, LanguageDetector, ScriptDetector, WhatlangDetector,
+};
+#[cfg(feature = "std")]
+pub use markdown::{parse_markdown, BlockKind, MarkdownBlock, MarkdownEstimator};
+pub use methods::method::{Accumulator, EstimationMethod, TextEstimator};
 pub use methods::method_basic::{BasicMethod, BasicParameters};
+#[cfg(feature = "std")]
+pub use methods::method_code::{is_code, CodeFeatures, CodeMethod, CodeParameters};
+#[cfg(feature = "std")]
+pub use methods::method_json::{JsonFeatures, JsonMethod, JsonParameters};
+#[cfg(feature = "multilingual")]
 pub use methods::method_multilingual::{MultilingualMethod, MultilingualMethodParameters};
+#[cfg(feature = "multilingual")]
 pub use methods::method_multilingual_simple::{
     MultilingualSimpleMethod, MultilingualSimpleMethodParameters,
 };
 pub use methods::method_simple::{SimpleMethod, SimpleParameters};
+#[cfg(feature = "std")]
+pub use params::{ParameterBundle, ParameterProfile};
+#[cfg(feature = "std")]
+pub use sampling::{
+    estimate_sampled_reader_with, estimate_sampled_with, SampleOptions, SampledEstimate,
+};
+#[cfg(feature = "std")]
+pub use streaming::{estimate_reader_with, StreamingEstimator, DETECTION_WINDOW};
+#[cfg(feature = "std")]
+pub use tools::{
+    parse_tools, render_tools, FunctionDefinition, ToolDefinition, ToolEstimator, ToolFeatures,
+    ToolParameters,
+};
+#[cfg(feature = "std")]
+pub use truncate::{truncate_with, TruncateSide, ELLIPSIS};
 
 // Enum for selecting estimation method
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum Method {
     #[default]
     Simple,
     Basic,
+    #[cfg(feature = "multilingual")]
     Multilingual,
+    #[cfg(feature = "multilingual")]
     MultilingualSimple,
+    Json,
+    Code,
+}
+
+impl Method {
+    pub const ALL: &'static [Method] = &[
+        Method::Simple,
+        Method::Basic,
+        #[cfg(feature = "multilingual")]
+        Method::Multilingual,
+        #[cfg(feature = "multilingual")]
+        Method::MultilingualSimple,
+        Method::Json,
+        Method::Code,
+    ];
+
+    /// Name used for the method in params files, Python and the CLI
+    pub fn name(&self) -> &'static str {
+        match self {
+            Method::Simple => "simple",
+            Method::Basic => "basic",
+            #[cfg(feature = "multilingual")]
+            Method::Multilingual => "multilingual",
+            #[cfg(feature = "multilingual")]
+            Method::MultilingualSimple => "multilingual_simple",
+            Method::Json => "json",
+            Method::Code => "code",
+        }
+    }
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
 }
 
-// Main estimation function - uses basic method by default
+impl FromStr for Method {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Method::ALL
+            .iter()
+            .copied()
+            .find(|method| method.name() == s.replace('-', "_"))
+            .ok_or_else(|| format!("unknown method: {s}"))
+    }
+}
+
+/// Method used when the caller picks none: multilingual simple, or simple
+/// when built without the `multilingual` feature
+#[cfg(feature = "multilingual")]
+pub const DEFAULT_METHOD: Method = Method::MultilingualSimple;
+#[cfg(not(feature = "multilingual"))]
+pub const DEFAULT_METHOD: Method = Method::Simple;
+
+/// Estimator type of `DEFAULT_METHOD`
+#[cfg(feature = "multilingual")]
+pub type DefaultMethod = MultilingualSimpleMethod;
+#[cfg(not(feature = "multilingual"))]
+pub type DefaultMethod = SimpleMethod;
+
+// Main estimation function - uses the default method
+#[cfg(feature = "std")]
 pub fn estimate_tokens(text: &str) -> usize {
-    let mut estimator = MultilingualSimpleMethod::new();
-    let _ = estimator.load_parameters(Path::new("params/multilingual_simple.toml"));
+    let estimator = DefaultMethod::new();
     estimator.estimate(text)
 }
 
+/// Estimate text from a reader, e.g. a multi-GB file or stdin, in constant memory
+#[cfg(feature = "std")]
+pub fn estimate_reader(reader: impl std::io::Read) -> std::io::Result<usize> {
+    estimate_reader_with(&DefaultMethod::new(), reader)
+}
+
+/// Estimate a very large text from windows spread across it, with the
+/// sampling error of the extrapolation
+#[cfg(feature = "std")]
+pub fn estimate_tokens_sampled(text: &str) -> SampledEstimate {
+    estimate_sampled_with(&DefaultMethod::new(), text, &SampleOptions::default())
+}
+
+/// Estimate prompt tokens of a chat conversation, including framing overhead
+#[cfg(feature = "std")]
+pub fn estimate_chat_tokens(messages: &[ChatMessage]) -> usize {
+    ChatEstimator::new().estimate(messages)
+}
+
+/// Estimate prompt tokens consumed by tool (function) definitions
+#[cfg(feature = "std")]
+pub fn estimate_tool_tokens(tools: &[ToolDefinition]) -> usize {
+    ToolEstimator::new().estimate(tools)
+}
+
+/// Estimate Markdown block by block, routing code blocks and tables to the code method
+#[cfg(feature = "std")]
+pub fn estimate_markdown_tokens(text: &str) -> usize {
+    MarkdownEstimator::new().estimate(text)
+}
+
+/// Truncate text to a token budget, keeping the head, the tail or both
+#[cfg(feature = "std")]
+pub fn truncate_to_tokens(text: &str, max_tokens: usize, side: TruncateSide) -> Cow<'_, str> {
+    truncate_with(&DefaultMethod::new(), text, max_tokens, side)
+}
+
+/// Split text into chunks estimated under `max_tokens`, repeating about
+/// `overlap` tokens between consecutive chunks
+#[cfg(feature = "std")]
+pub fn chunk(text: &str, max_tokens: usize, overlap: usize) -> Vec<&str> {
+    let options = ChunkOptions {
+        overlap,
+        ..ChunkOptions::new(max_tokens)
+    };
+    chunk_with(&DefaultMethod::new(), text, &options)
+}
+
+/// Detect the ISO 639-3 language code of text, or "unknown"
+#[cfg(feature = "multilingual")]
+pub fn detect_language(text: &str) -> String {
+    WhatlangDetector
+        .detect(text)
+        .unwrap_or("unknown")
+        .to_string()
+}
+
 // Python bindings
+/// Running estimate of growing text, fed with `push(str)` or `push_bytes(bytes)`
+#[cfg(feature = "pyo3")]
+#[pyclass(name = "StreamingEstimator", unsendable)]
+struct PyStreamingEstimator {
+    inner: StreamingEstimator<'static>,
+}
+
+#[cfg(feature = "pyo3")]
+#[pymethods]
+impl PyStreamingEstimator {
+    #[new]
+    #[pyo3(signature = (invalid = "lossy", encoding = "utf-8"))]
+    fn new(invalid: &str, encoding: &str) -> PyResult<Self> {
+        let invalid = invalid
+            .parse::<InvalidPolicy>()
+            .map_err(pyo3::exceptions::PyValueError::new_err)?;
+        let encoding = encoding
+            .parse::<Encoding>()
+            .map_err(pyo3::exceptions::PyValueError::new_err)?;
+        Ok(Self {
+            inner: StreamingEstimator::default()
+                .with_encoding(encoding)
+                .with_invalid(invalid),
+        })
+    }
+
+    fn push(&mut self, text: &str) {
+        self.inner.push(text);
+    }
+
+    fn push_bytes(&mut self, data: &[u8]) -> PyResult<()> {
+        self.inner
+            .push_bytes(data)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))
+    }
+
+    fn estimate(&self) -> usize {
+        self.inner.estimate()
+    }
+
+    fn finish(&mut self) -> PyResult<usize> {
+        self.inner
+            .finish()
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.len()
+    }
+}
+
 #[cfg(feature = "pyo3")]
 #[pymodule]
 fn _skimtoken_core(m: &Bound<'_, PyModule>) -> PyResult<()> {
@@ -66,13 +331,8 @@ fn _skimtoken_core(m: &Bound<'_, PyModule>) -> PyResult<()> {
     #[pyfn(m)]
     #[pyo3(name = "estimate_tokens_simple")]
     fn estimate_tokens_simple_py(text: &Bound<'_, PyAny>) -> PyResult<usize> {
-        let text_str = if let Ok(s) = text.extract::<String>() {
-            s
-        } else {
-            String::new()
-        };
-        let mut estimator = SimpleMethod::new();
-        let _ = estimator.load_parameters(Path::new("params/simple.toml"));
+        let text_str = text.extract::<String>().unwrap_or_default();
+        let estimator = SimpleMethod::new();
         Ok(estimator.estimate(&text_str))
     }
 
@@ -80,41 +340,60 @@ fn _skimtoken_core(m: &Bound<'_, PyModule>) -> PyResult<()> {
     #[pyfn(m)]
     #[pyo3(name = "estimate_tokens_basic")]
     fn estimate_tokens_basic_py(text: &Bound<'_, PyAny>) -> PyResult<usize> {
-        let text_str = if let Ok(s) = text.extract::<String>() {
-            s
-        } else {
-            String::new()
-        };
-        let mut estimator = BasicMethod::new();
-        let _ = estimator.load_parameters(Path::new("params/basic.toml"));
+        let text_str = text.extract::<String>().unwrap_or_default();
+        let estimator = BasicMethod::new();
         Ok(estimator.estimate(&text_str))
     }
 
     // Multilingual method estimation
     #[pyfn(m)]
-    #[pyo3(name = "estimate_tokens_multilingual")]
-    fn estimate_tokens_multilingual_py(text: &Bound<'_, PyAny>) -> PyResult<usize> {
-        let text_str = if let Ok(s) = text.extract::<String>() {
-            s
-        } else {
-            String::new()
-        };
-        let mut estimator = MultilingualMethod::new();
-        let _ = estimator.load_parameters(Path::new("params/multilingual.toml"));
-        Ok(estimator.estimate(&text_str))
+    #[pyo3(name = "estimate_tokens_multilingual", signature = (text, language = None))]
+    fn estimate_tokens_multilingual_py(
+        text: &Bound<'_, PyAny>,
+        language: Option<&str>,
+    ) -> PyResult<usize> {
+        let text_str = text.extract::<String>().unwrap_or_default();
+        let estimator = MultilingualMethod::new();
+        match language {
+            Some(language) => estimator
+                .estimate_with_language(&text_str, language)
+                .map_err(pyo3::exceptions::PyValueError::new_err),
+            None => Ok(estimator.estimate(&text_str)),
+        }
     }
 
     // Multilingual simple method estimation
     #[pyfn(m)]
-    #[pyo3(name = "estimate_tokens_multilingual_simple")]
-    fn estimate_tokens_multilingual_simple_py(text: &Bound<'_, PyAny>) -> PyResult<usize> {
-        let text_str = if let Ok(s) = text.extract::<String>() {
-            s
-        } else {
-            String::new()
-        };
-        let mut estimator = MultilingualSimpleMethod::new();
-        let _ = estimator.load_parameters(Path::new("params/multilingual_simple.toml"));
+    #[pyo3(name = "estimate_tokens_multilingual_simple", signature = (text, language = None))]
+    fn estimate_tokens_multilingual_simple_py(
+        text: &Bound<'_, PyAny>,
+        language: Option<&str>,
+    ) -> PyResult<usize> {
+        let text_str = text.extract::<String>().unwrap_or_default();
+        let estimator = MultilingualSimpleMethod::new();
+        match language {
+            Some(language) => estimator
+                .estimate_with_language(&text_str, language)
+                .map_err(pyo3::exceptions::PyValueError::new_err),
+            None => Ok(estimator.estimate(&text_str)),
+        }
+    }
+
+    // JSON-aware estimation
+    #[pyfn(m)]
+    #[pyo3(name = "estimate_tokens_json")]
+    fn estimate_tokens_json_py(text: &Bound<'_, PyAny>) -> PyResult<usize> {
+        let text_str = text.extract::<String>().unwrap_or_default();
+        let estimator = JsonMethod::new();
+        Ok(estimator.estimate(&text_str))
+    }
+
+    // Source code estimation
+    #[pyfn(m)]
+    #[pyo3(name = "estimate_tokens_code")]
+    fn estimate_tokens_code_py(text: &Bound<'_, PyAny>) -> PyResult<usize> {
+        let text_str = text.extract::<String>().unwrap_or_default();
+        let estimator = CodeMethod::new();
         Ok(estimator.estimate(&text_str))
     }
 
@@ -122,11 +401,7 @@ fn _skimtoken_core(m: &Bound<'_, PyModule>) -> PyResult<()> {
     #[pyfn(m)]
     #[pyo3(name = "count_simple")]
     fn count_simple_py(text: &Bound<'_, PyAny>) -> PyResult<usize> {
-        let text_str = if let Ok(s) = text.extract::<String>() {
-            s
-        } else {
-            String::new()
-        };
+        let text_str = text.extract::<String>().unwrap_or_default();
         let estimator = SimpleMethod::new();
         Ok(estimator.count(&text_str))
     }
@@ -134,11 +409,7 @@ fn _skimtoken_core(m: &Bound<'_, PyModule>) -> PyResult<()> {
     #[pyfn(m)]
     #[pyo3(name = "count_basic")]
     fn count_basic_py(text: &Bound<'_, PyAny>) -> PyResult<(usize, usize, f32, usize)> {
-        let text_str = if let Ok(s) = text.extract::<String>() {
-            s
-        } else {
-            String::new()
-        };
+        let text_str = text.extract::<String>().unwrap_or_default();
         let estimator = BasicMethod::new();
         let features = estimator.count(&text_str);
         Ok((
@@ -154,11 +425,7 @@ fn _skimtoken_core(m: &Bound<'_, PyModule>) -> PyResult<()> {
     fn count_multilingual_py(
         text: &Bound<'_, PyAny>,
     ) -> PyResult<(usize, usize, f32, usize, String)> {
-        let text_str = if let Ok(s) = text.extract::<String>() {
-            s
-        } else {
-            String::new()
-        };
+        let text_str = text.extract::<String>().unwrap_or_default();
         let estimator = MultilingualMethod::new();
         let features = estimator.count(&text_str);
         Ok((
@@ -173,37 +440,346 @@ fn _skimtoken_core(m: &Bound<'_, PyModule>) -> PyResult<()> {
     #[pyfn(m)]
     #[pyo3(name = "count_multilingual_simple")]
     fn count_multilingual_simple_py(text: &Bound<'_, PyAny>) -> PyResult<(usize, String)> {
-        let text_str = if let Ok(s) = text.extract::<String>() {
-            s
-        } else {
-            String::new()
-        };
+        let text_str = text.extract::<String>().unwrap_or_default();
         let estimator = MultilingualSimpleMethod::new();
         let features = estimator.count(&text_str);
         Ok((features.char_count, features.language))
     }
 
-    // Language detection function
+    // Chat conversation estimation
     #[pyfn(m)]
-    #[pyo3(name = "detect_language")]
-    fn detect_language_py(text: &Bound<'_, PyAny>) -> PyResult<String> {
-        let text_str = if let Ok(s) = text.extract::<String>() {
+    #[pyo3(name = "estimate_chat_tokens")]
+    fn estimate_chat_tokens_py(messages: &Bound<'_, PyAny>) -> PyResult<usize> {
+        // Accept a JSON string or Python messages, round-tripped through json
+        let json = if let Ok(s) = messages.extract::<String>() {
             s
         } else {
-            String::new()
+            let json = messages.py().import("json")?;
+            json.call_method1("dumps", (messages,))?
+                .extract::<String>()?
         };
-        use whatlang::detect;
-        let language = detect(&text_str)
-            .map(|info| info.lang().code())
-            .unwrap_or("unknown")
-            .to_string();
-        Ok(language)
+        let request = parse_chat_request(&json)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        Ok(ChatEstimator::new().estimate_request(&request))
+    }
+
+    // Tool definition estimation
+    #[pyfn(m)]
+    #[pyo3(name = "estimate_tool_tokens")]
+    fn estimate_tool_tokens_py(tools: &Bound<'_, PyAny>) -> PyResult<usize> {
+        let json = if let Ok(s) = tools.extract::<String>() {
+            s
+        } else {
+            let json = tools.py().import("json")?;
+            json.call_method1("dumps", (tools,))?.extract::<String>()?
+        };
+        let tools = parse_tools(&json)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        Ok(estimate_tool_tokens(&tools))
+    }
+
+    // Markdown estimation
+    #[pyfn(m)]
+    #[pyo3(name = "estimate_markdown_tokens")]
+    fn estimate_markdown_tokens_py(text: &Bound<'_, PyAny>) -> PyResult<usize> {
+        let text_str = text.extract::<String>().unwrap_or_default();
+        Ok(estimate_markdown_tokens(&text_str))
     }
 
+    #[pyfn(m)]
+    #[pyo3(name = "markdown_breakdown")]
+    fn markdown_breakdown_py<'py>(text: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+        let text_str = text.extract::<String>().unwrap_or_default();
+        // Blocks are handed over as JSON, the same way chat input comes in
+        let blocks = MarkdownEstimator::new().breakdown(&text_str);
+        let blocks = serde_json::to_string(&blocks)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        text.py().import("json")?.call_method1("loads", (blocks,))
+    }
+
+    // HTML/XML estimation
+    #[pyfn(m)]
+    #[pyo3(name = "estimate_html_tokens")]
+    fn estimate_html_tokens_py(text: &Bound<'_, PyAny>) -> PyResult<(usize, usize, usize, usize)> {
+        let text_str = text.extract::<String>().unwrap_or_default();
+        let estimate = HtmlEstimator::new().estimate(&text_str);
+        Ok((
+            estimate.raw_tokens,
+            estimate.markup_tokens,
+            estimate.text_tokens,
+            estimate.stripped_tokens,
+        ))
+    }
+
+    #[pyfn(m)]
+    #[pyo3(name = "strip_html")]
+    fn strip_html_py(text: &Bound<'_, PyAny>) -> PyResult<String> {
+        let text_str = text.extract::<String>().unwrap_or_default();
+        Ok(strip_html(&text_str))
+    }
+
+    // Truncation to a token budget
+    #[pyfn(m)]
+    #[pyo3(name = "truncate_to_tokens", signature = (text, max_tokens, side = "head"))]
+    fn truncate_to_tokens_py(text: &str, max_tokens: usize, side: &str) -> PyResult<String> {
+        let side = side
+            .parse::<TruncateSide>()
+            .map_err(pyo3::exceptions::PyValueError::new_err)?;
+        Ok(truncate_to_tokens(text, max_tokens, side).into_owned())
+    }
+
+    // Sampled estimation of very large text
+    #[pyfn(m)]
+    #[pyo3(
+        name = "estimate_tokens_sampled",
+        signature = (text, windows = 32, window_bytes = 4096)
+    )]
+    fn estimate_tokens_sampled_py(
+        text: &str,
+        windows: usize,
+        window_bytes: usize,
+    ) -> PyResult<(usize, f64)> {
+        if window_bytes == 0 {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "window_bytes must be at least 1",
+            ));
+        }
+        let options = SampleOptions {
+            windows,
+            window_bytes,
+        };
+        let estimate = estimate_sampled_with(&MultilingualSimpleMethod::new(), text, &options);
+        Ok((estimate.tokens, estimate.std_error))
+    }
+
+    // Chunking under a token budget
+    #[pyfn(m)]
+    #[pyo3(name = "chunk", signature = (text, max_tokens, overlap = 0, margin = 0.0))]
+    fn chunk_py(text: &str, max_tokens: usize, overlap: usize, margin: f64) -> Vec<String> {
+        let options = ChunkOptions {
+            overlap,
+            margin,
+            ..ChunkOptions::new(max_tokens)
+        };
+        chunk_with(&MultilingualSimpleMethod::new(), text, &options)
+            .into_iter()
+            .map(str::to_string)
+            .collect()
+    }
+
+    // Language detection function
+    #[pyfn(m)]
+    #[pyo3(name = "detect_language")]
+    fn detect_language_py(text: &Bound<'_, PyAny>) -> PyResult<String> {
+        let text_str = text.extract::<String>().unwrap_or_default();
+        Ok(detect_language(&text_str))
+    }
+
+    m.add_class::<PyStreamingEstimator>()?;
+
     Ok(())
 }
 
-#[cfg(test)]
+// Node.js bindings, mirroring the Python ones; typings are in node/index.d.ts
+#[cfg(feature = "node")]
+// napi-derive leaves out registration in test builds
+#[cfg_attr(test, allow(dead_code))]
+mod node {
+    use super::*;
+    use napi::bindgen_prelude::{AsyncTask, Error, Result};
+    use napi::{Env, Task};
+    use napi_derive::napi;
+    use std::sync::OnceLock;
+
+    type SharedEstimator = &'static (dyn TextEstimator + Send + Sync);
+
+    /// Estimators with the compiled-in parameters, built once per process
+    fn cached(method: Method) -> SharedEstimator {
+        static ESTIMATORS: OnceLock<Vec<Box<dyn TextEstimator + Send + Sync>>> = OnceLock::new();
+        let estimators = ESTIMATORS.get_or_init(|| {
+            let bundle = ParameterBundle::default();
+            Method::ALL
+                .iter()
+                .map(|method| bundle.text_estimator(*method))
+                .collect()
+        });
+        let index = Method::ALL.iter().position(|m| *m == method).unwrap_or(0);
+        estimators[index].as_ref()
+    }
+
+    fn estimator(method: Option<String>) -> Result<SharedEstimator> {
+        let method = match method {
+            Some(name) => name.parse::<Method>().map_err(Error::from_reason)?,
+            None => Method::MultilingualSimple,
+        };
+        Ok(cached(method))
+    }
+
+    #[napi]
+    pub fn estimate_tokens(text: String, method: Option<String>) -> Result<u32> {
+        Ok(estimator(method)?.estimate_text(&text) as u32)
+    }
+
+    #[napi]
+    pub fn estimate_tokens_simple(text: String) -> u32 {
+        cached(Method::Simple).estimate_text(&text) as u32
+    }
+
+    #[napi]
+    pub fn estimate_tokens_basic(text: String) -> u32 {
+        cached(Method::Basic).estimate_text(&text) as u32
+    }
+
+    #[napi]
+    pub fn estimate_tokens_multilingual(text: String) -> u32 {
+        cached(Method::Multilingual).estimate_text(&text) as u32
+    }
+
+    #[napi]
+    pub fn estimate_tokens_multilingual_simple(text: String) -> u32 {
+        cached(Method::MultilingualSimple).estimate_text(&text) as u32
+    }
+
+    #[napi]
+    pub fn estimate_tokens_json(text: String) -> u32 {
+        cached(Method::Json).estimate_text(&text) as u32
+    }
+
+    #[napi]
+    pub fn estimate_tokens_code(text: String) -> u32 {
+        cached(Method::Code).estimate_text(&text) as u32
+    }
+
+    #[napi(object)]
+    pub struct BasicCounts {
+        pub char_count: u32,
+        pub word_count: u32,
+        pub avg_word_length: f64,
+        pub space_count: u32,
+    }
+
+    #[napi(object)]
+    pub struct MultilingualCounts {
+        pub char_count: u32,
+        pub word_count: u32,
+        pub avg_word_length: f64,
+        pub space_count: u32,
+        pub language: String,
+    }
+
+    #[napi(object)]
+    pub struct MultilingualSimpleCounts {
+        pub char_count: u32,
+        pub language: String,
+    }
+
+    // Feature extraction functions for optimization
+    #[napi]
+    pub fn count_simple(text: String) -> u32 {
+        SimpleMethod::new().count(&text) as u32
+    }
+
+    #[napi]
+    pub fn count_basic(text: String) -> BasicCounts {
+        let features = BasicMethod::new().count(&text);
+        BasicCounts {
+            char_count: features.char_count as u32,
+            word_count: features.word_count as u32,
+            avg_word_length: f64::from(features.avg_word_length),
+            space_count: features.space_count as u32,
+        }
+    }
+
+    #[napi]
+    pub fn count_multilingual(text: String) -> MultilingualCounts {
+        let features = MultilingualMethod::new().count(&text);
+        let basic = features.basic_features;
+        MultilingualCounts {
+            char_count: basic.char_count as u32,
+            word_count: basic.word_count as u32,
+            avg_word_length: f64::from(basic.avg_word_length),
+            space_count: basic.space_count as u32,
+            language: features.language,
+        }
+    }
+
+    #[napi]
+    pub fn count_multilingual_simple(text: String) -> MultilingualSimpleCounts {
+        let features = MultilingualSimpleMethod::new().count(&text);
+        MultilingualSimpleCounts {
+            char_count: features.char_count as u32,
+            language: features.language,
+        }
+    }
+
+    #[napi(js_name = "detectLanguage")]
+    pub fn detect_language_js(text: String) -> String {
+        detect_language(&text)
+    }
+
+    /// Estimates a batch on the libuv thread pool
+    pub struct EstimateBatch {
+        estimator: SharedEstimator,
+        texts: Vec<String>,
+    }
+
+    impl Task for EstimateBatch {
+        type Output = Vec<u32>;
+        type JsValue = Vec<u32>;
+
+        fn compute(&mut self) -> Result<Self::Output> {
+            Ok(self
+                .texts
+                .iter()
+                .map(|text| self.estimator.estimate_text(text) as u32)
+                .collect())
+        }
+
+        fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+            Ok(output)
+        }
+    }
+
+    #[napi(ts_return_type = "Promise<number[]>")]
+    pub fn estimate_tokens_batch(
+        texts: Vec<String>,
+        method: Option<String>,
+    ) -> Result<AsyncTask<EstimateBatch>> {
+        Ok(AsyncTask::new(EstimateBatch {
+            estimator: estimator(method)?,
+            texts,
+        }))
+    }
+
+    /// Detects languages of a batch on the libuv thread pool
+    pub struct DetectLanguageBatch {
+        texts: Vec<String>,
+    }
+
+    impl Task for DetectLanguageBatch {
+        type Output = Vec<String>;
+        type JsValue = Vec<String>;
+
+        fn compute(&mut self) -> Result<Self::Output> {
+            Ok(self
+                .texts
+                .iter()
+                .map(|text| detect_language(text))
+                .collect())
+        }
+
+        fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+            Ok(output)
+        }
+    }
+
+    #[napi(ts_return_type = "Promise<string[]>")]
+    pub fn detect_language_batch(texts: Vec<String>) -> AsyncTask<DetectLanguageBatch> {
+        AsyncTask::new(DetectLanguageBatch { texts })
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
 
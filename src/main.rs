@@ -1,43 +1,742 @@
-use clap::Parser;
-use skimtoken::estimate_tokens;
-use std::fs;
-use std::io::{self, Read};
+use clap::{Parser, Subcommand};
+use memmap2::Mmap;
+use skimtoken::{
+    chunk_with, decode_bytes, estimate_sampled_reader_with, estimate_sampled_with, evaluate, fit,
+    fit_tools, load_dataset, load_tool_dataset, parse_chat_request, sniff_bom, ChatEstimator,
+    ChunkOptions, Encoding, EvalReport, FitOptions, HtmlEstimator, InvalidPolicy, Loss,
+    MarkdownEstimator, Method, Metrics, ParameterBundle, Sample, SampleOptions, StreamingEstimator,
+    TextEstimator, DEFAULT_METHOD,
+};
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
 
 #[derive(Parser)]
 #[command(version, about = "Estimate token count for text")]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Text to estimate tokens for
     text: Option<String>,
 
-    /// Read text from file
+    /// Read text from file. Files over 64 MiB are streamed, with the language
+    /// detected from their first 1 KiB
     #[arg(short, long)]
     file: Option<String>,
+
+    /// Estimation method (defaults to multilingual_simple, or simple without language detection)
+    #[arg(short, long)]
+    method: Option<Method>,
+
+    /// Language of the text for the multilingual methods, e.g. ja, jpn or
+    /// ja-JP, instead of detecting it
+    #[cfg(feature = "multilingual")]
+    #[arg(long, value_name = "TAG")]
+    lang: Option<String>,
+
+    /// Extrapolate from this many windows spread across the input instead of
+    /// reading all of it; the sampling error is printed to stderr
+    #[arg(long, num_args = 0..=1, default_missing_value = "32", value_name = "WINDOWS")]
+    sample: Option<usize>,
+
+    /// Encoding of file and stdin input: utf-8, utf-16, utf-16le, utf-16be or
+    /// latin-1. A byte order mark overrides it, except for latin-1
+    #[arg(long, global = true, default_value = "utf-8")]
+    encoding: Encoding,
+
+    /// What to do with invalid input: replace it (lossy), count each byte as
+    /// a token (bytes, not for markdown, html or split), drop it (skip) or stop
+    /// with an error (error)
+    #[arg(long, global = true, default_value = "lossy")]
+    invalid: InvalidPolicy,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Fit method parameters to a labeled JSONL dataset
+    Fit {
+        /// JSONL dataset with `text` and `token_len` fields
+        #[arg(short, long)]
+        dataset: PathBuf,
+
+        /// Methods to fit (defaults to all)
+        #[arg(short, long)]
+        method: Vec<Method>,
+
+        /// Params file to update; other sections are kept as they are
+        #[arg(short, long, default_value = "params/bundle.toml")]
+        output: PathBuf,
+
+        /// Constrain feature coefficients to be non-negative
+        #[arg(long)]
+        non_negative: bool,
+
+        /// Fit a single parameter set instead of one per detected language
+        #[arg(long)]
+        no_per_language: bool,
+
+        /// Minimize pinball loss at this quantile instead of squared error;
+        /// 0.95 keeps underestimates to about 5% of samples
+        #[arg(long, value_parser = parse_quantile, conflicts_with = "under_penalty")]
+        quantile: Option<f64>,
+
+        /// Weight underestimates this many times more than overestimates
+        #[arg(long, value_parser = parse_under_penalty)]
+        under_penalty: Option<f64>,
+
+        /// Languages with fewer samples fall back to the default parameters
+        #[arg(long, default_value_t = 10)]
+        min_samples: usize,
+
+        /// Pull per-language coefficients toward the defaults, in pseudo-samples
+        #[arg(long, default_value_t = 10.0)]
+        shrinkage: f64,
+
+        /// Fit the tool definition calibration from a dataset of `tools` and
+        /// `token_len`, estimating schema text with the first --method
+        #[arg(long)]
+        tools: bool,
+    },
+
+    /// Report accuracy metrics of a method on a labeled JSONL dataset
+    Eval {
+        /// JSONL dataset with `text` and `token_len` fields
+        #[arg(short, long)]
+        dataset: PathBuf,
+
+        /// Method to evaluate
+        #[arg(short, long, default_value_t = DEFAULT_METHOD)]
+        method: Method,
+
+        /// Params file to evaluate instead of the compiled-in defaults
+        #[arg(short, long)]
+        params: Option<PathBuf>,
+
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Estimate prompt tokens of an OpenAI-style chat request, including tools
+    Chat {
+        /// Read the request from file instead of stdin
+        #[arg(short, long)]
+        file: Option<PathBuf>,
+
+        /// Method used for message contents
+        #[arg(short, long, default_value_t = DEFAULT_METHOD)]
+        method: Method,
+
+        /// Params file to use instead of the compiled-in defaults
+        #[arg(short, long)]
+        params: Option<PathBuf>,
+
+        /// Tokenizer profile from the params file
+        #[arg(long)]
+        profile: Option<String>,
+    },
+
+    /// Break down a Markdown document's estimate by block
+    Markdown {
+        /// Read the document from file instead of stdin
+        #[arg(short, long)]
+        file: Option<PathBuf>,
+
+        /// Method used for prose blocks
+        #[arg(short, long, default_value_t = DEFAULT_METHOD)]
+        method: Method,
+
+        /// Params file to use instead of the compiled-in defaults
+        #[arg(short, long)]
+        params: Option<PathBuf>,
+
+        /// Tokenizer profile from the params file
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Print the blocks as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Estimate an HTML or XML document raw and with markup stripped
+    Html {
+        /// Read the document from file instead of stdin
+        #[arg(short, long)]
+        file: Option<PathBuf>,
+
+        /// Method used for text
+        #[arg(short, long, default_value_t = DEFAULT_METHOD)]
+        method: Method,
+
+        /// Params file to use instead of the compiled-in defaults
+        #[arg(short, long)]
+        params: Option<PathBuf>,
+
+        /// Tokenizer profile from the params file
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Print the estimate as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Split a document into chunks estimated under a token budget
+    Split {
+        /// Read the document from file instead of stdin
+        #[arg(short, long)]
+        file: Option<PathBuf>,
+
+        /// Directory to write chunk-0001.txt, chunk-0002.txt, ... to
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Largest estimate of a chunk
+        #[arg(long, value_parser = parse_max_tokens)]
+        max_tokens: usize,
+
+        /// Tokens repeated at the start of the next chunk
+        #[arg(long, default_value_t = 0)]
+        overlap: usize,
+
+        /// Share of --max-tokens held back for estimation error, e.g. 0.15
+        #[arg(long, default_value_t = 0.0)]
+        margin: f64,
+
+        /// Method used to estimate chunks
+        #[arg(short, long, default_value_t = DEFAULT_METHOD)]
+        method: Method,
+
+        /// Params file to use instead of the compiled-in defaults
+        #[arg(short, long)]
+        params: Option<PathBuf>,
+
+        /// Tokenizer profile from the params file
+        #[arg(long)]
+        profile: Option<String>,
+    },
 }
 
 fn main() {
     let args = Args::parse();
 
-    let text = if let Some(file) = args.file {
-        fs::read_to_string(file).unwrap_or_else(|e| {
-            eprintln!("Error reading file: {e}");
+    let input = Input {
+        encoding: args.encoding,
+        invalid: args.invalid,
+    };
+    if let Some(command) = args.command {
+        run_command(command, input);
+        return;
+    }
+
+    let method = args.method.unwrap_or(DEFAULT_METHOD);
+    let bundle = ParameterBundle::default();
+    #[cfg(feature = "multilingual")]
+    let estimator = match &args.lang {
+        Some(lang) => bundle
+            .text_estimator_with_language(method, lang)
+            .unwrap_or_else(|e| {
+                eprintln!("Invalid --lang: {e}");
+                std::process::exit(1);
+            }),
+        None => bundle.text_estimator(method),
+    };
+    #[cfg(not(feature = "multilingual"))]
+    let estimator = bundle.text_estimator(method);
+
+    if let Some(windows) = args.sample {
+        let options = SampleOptions {
+            windows,
+            ..SampleOptions::default()
+        };
+        // Only UTF-8 files can be seeked into; other input is sampled in memory
+        let estimate = match args.file {
+            Some(file) if input.encoding == Encoding::Utf8 && !has_utf16_bom(&file) => {
+                File::open(file)
+                    .and_then(|file| {
+                        estimate_sampled_reader_with(
+                            estimator.as_ref(),
+                            file,
+                            &options,
+                            input.invalid,
+                        )
+                    })
+                    .unwrap_or_else(|e| {
+                        eprintln!("Error reading input: {e}");
+                        std::process::exit(1);
+                    })
+            }
+            file => {
+                // --file takes precedence over text, as without --sample
+                let (text, invalid) = match (file, args.text) {
+                    (None, Some(text)) => (text, 0),
+                    (file, _) => read_input(file.as_deref().map(Path::new), input),
+                };
+                let mut estimate = estimate_sampled_with(estimator.as_ref(), &text, &options);
+                estimate.tokens += invalid;
+                estimate
+            }
+        };
+        if estimate.total_bytes == 0 {
+            eprintln!("No text provided");
             std::process::exit(1);
-        })
-    } else if let Some(text) = args.text {
-        text
-    } else if atty::is(atty::Stream::Stdin) {
+        }
+        println!("{}", estimate.tokens);
+        eprintln!(
+            "Sampled {} of {} bytes, standard error ±{:.0} ({:.2}%)",
+            estimate.sampled_bytes,
+            estimate.total_bytes,
+            estimate.std_error,
+            estimate.relative_error() * 100.0
+        );
+        return;
+    }
+
+    // Text given as an argument gets the full estimate rather than the
+    // streaming approximation
+    if let (None, Some(text)) = (&args.file, &args.text) {
+        if text.is_empty() {
+            eprintln!("No text provided");
+            std::process::exit(1);
+        }
+        println!("{}", estimator.estimate_text(text));
+        return;
+    }
+
+    // Files are read through a memory map: those up to 64 MiB get the full
+    // estimate, larger ones are streamed
+    if let Some(file) = args.file {
+        let map = map_file(Path::new(&file)).unwrap_or_else(|e| {
+            eprintln!("Error reading input: {e}");
+            std::process::exit(1);
+        });
+        if map.is_empty() {
+            eprintln!("No text provided");
+            std::process::exit(1);
+        }
+        let tokens = if map.len() as u64 <= FULL_ESTIMATE_LIMIT {
+            estimate_whole(estimator.as_ref(), &map, input)
+        } else {
+            let mut stream = StreamingEstimator::new(estimator.as_ref())
+                .with_encoding(input.encoding)
+                .with_invalid(input.invalid);
+            push_mapped(&mut stream, &map).and_then(|()| stream.finish())
+        };
+        let tokens = tokens.unwrap_or_else(|e| {
+            eprintln!("Error reading input: {e}");
+            std::process::exit(1);
+        });
+        println!("{tokens}");
+        return;
+    }
+
+    // Stdin is streamed, so its size is not limited by memory; the language
+    // is detected from the start of the stream
+    let mut stream = StreamingEstimator::new(estimator.as_ref())
+        .with_encoding(input.encoding)
+        .with_invalid(input.invalid);
+    let read = if atty::is(atty::Stream::Stdin) {
+        Ok(())
+    } else {
+        stream.read_from(io::stdin().lock()).map(drop)
+    };
+    let tokens = read.and_then(|()| stream.finish()).unwrap_or_else(|e| {
+        eprintln!("Error reading input: {e}");
+        std::process::exit(1);
+    });
+
+    if stream.is_empty() {
         eprintln!("No text provided");
         std::process::exit(1);
+    }
+    println!("{tokens}");
+}
+
+/// Files up to this size get the full estimate
+const FULL_ESTIMATE_LIMIT: u64 = 64 << 20;
+
+/// Full estimate of `bytes`, decoded as `input` says. Invalid bytes count as
+/// in `StreamingEstimator`
+fn estimate_whole(estimator: &dyn TextEstimator, bytes: &[u8], input: Input) -> io::Result<usize> {
+    // Valid UTF-8 is estimated in place rather than copied
+    if input.encoding == Encoding::Utf8 {
+        let body = match sniff_bom(bytes) {
+            Some((Encoding::Utf8, len)) => &bytes[len..],
+            _ => bytes,
+        };
+        if let Ok(text) = std::str::from_utf8(body) {
+            return Ok(estimator.estimate_text(text));
+        }
+    }
+
+    let (text, invalid) = decode_bytes(bytes, input.encoding, input.invalid)?;
+    Ok(estimator.estimate_text(&text) + invalid)
+}
+
+fn has_utf16_bom(path: &str) -> bool {
+    let mut head = [0; 2];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut head))
+        .is_ok_and(|()| sniff_bom(&head).is_some_and(|(encoding, _)| encoding != Encoding::Utf8))
+}
+
+/// Map a file instead of copying it to the heap
+fn map_file(path: &Path) -> io::Result<Mmap> {
+    let file = File::open(path)?;
+    // SAFETY: the map is only read. If another process truncates the file
+    // meanwhile, reading it faults, as with any memory-mapped reader
+    unsafe { Mmap::map(&file) }
+}
+
+/// Bytes of a mapped file validated and estimated at a time
+const MAPPED_BLOCK: usize = 1 << 20;
+
+fn push_mapped(
+    stream: &mut StreamingEstimator<dyn TextEstimator + Send + Sync>,
+    map: &Mmap,
+) -> io::Result<()> {
+    for block in map.chunks(MAPPED_BLOCK) {
+        stream.push_bytes(block)?;
+    }
+    Ok(())
+}
+
+/// How file and stdin bytes are decoded
+#[derive(Clone, Copy)]
+struct Input {
+    encoding: Encoding,
+    invalid: InvalidPolicy,
+}
+
+fn run_command(command: Command, input: Input) {
+    match command {
+        Command::Fit {
+            dataset,
+            method,
+            output,
+            non_negative,
+            no_per_language,
+            quantile,
+            under_penalty,
+            min_samples,
+            shrinkage,
+            tools,
+        } => {
+            let mut bundle = if output.exists() {
+                load_bundle(&output)
+            } else {
+                ParameterBundle::default()
+            };
+
+            let options = FitOptions {
+                non_negative,
+                per_language: !no_per_language,
+                loss: match (quantile, under_penalty) {
+                    (Some(q), _) => Loss::Quantile(q),
+                    (None, Some(under_penalty)) => Loss::Asymmetric { under_penalty },
+                    (None, None) => Loss::Squared,
+                },
+                min_samples,
+                shrinkage,
+            };
+            if tools {
+                let samples = load_tool_dataset(&dataset).unwrap_or_else(|e| {
+                    eprintln!("Error reading dataset: {e}");
+                    std::process::exit(1);
+                });
+                if samples.is_empty() {
+                    eprintln!("Dataset is empty");
+                    std::process::exit(1);
+                }
+                let method = method.first().copied().unwrap_or(DEFAULT_METHOD);
+                eprintln!("Fitting tools on {} samples", samples.len());
+                match fit_tools(&samples, method, &bundle, &options) {
+                    Ok(Some(params)) => bundle.tools = params,
+                    Ok(None) => {
+                        eprintln!("Fewer than {min_samples} samples; [tools] is unchanged")
+                    }
+                    Err(e) => {
+                        eprintln!("Invalid fit options: {e}");
+                        std::process::exit(1);
+                    }
+                }
+            } else {
+                let samples = load_samples(&dataset);
+                let methods = if method.is_empty() {
+                    Method::ALL.to_vec()
+                } else {
+                    method
+                };
+                for method in methods {
+                    eprintln!("Fitting {method} on {} samples", samples.len());
+                    let fitted = fit(method, &samples, &options, &mut bundle).unwrap_or_else(|e| {
+                        eprintln!("Invalid fit options: {e}");
+                        std::process::exit(1);
+                    });
+                    if !fitted {
+                        eprintln!(
+                            "Fewer than {min_samples} {method} samples; [{method}] is unchanged"
+                        );
+                    }
+                }
+            }
+
+            bundle.save(&output).unwrap_or_else(|e| {
+                eprintln!("Error writing params: {e}");
+                std::process::exit(1);
+            });
+            eprintln!("Parameters saved to {}", output.display());
+        }
+        Command::Eval {
+            dataset,
+            method,
+            params,
+            json,
+        } => {
+            let samples = load_samples(&dataset);
+            let bundle = resolve_bundle(params.as_deref(), None);
+            let report = evaluate(method, &bundle, &samples);
+            // A reader such as `head` may close the pipe early; that is not an error
+            match print_report(&mut io::stdout().lock(), &report, json) {
+                Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+                    eprintln!("Error writing report: {e}");
+                    std::process::exit(1);
+                }
+                _ => {}
+            }
+        }
+        Command::Chat {
+            file,
+            method,
+            params,
+            profile,
+        } => {
+            let bundle = resolve_bundle(params.as_deref(), profile.as_deref());
+            let (text, invalid) = read_input(file.as_deref(), input);
+            let request = parse_chat_request(&text).unwrap_or_else(|e| {
+                eprintln!("Error parsing chat request: {e}");
+                std::process::exit(1);
+            });
+
+            let estimator = ChatEstimator::from_bundle(&bundle, method);
+            println!("{}", estimator.estimate_request(&request) + invalid);
+        }
+        Command::Markdown {
+            file,
+            method,
+            params,
+            profile,
+            json,
+        } => {
+            let bundle = resolve_bundle(params.as_deref(), profile.as_deref());
+            let text = read_text(file.as_deref(), input, "markdown");
+            let blocks = MarkdownEstimator::from_bundle(&bundle, method).breakdown(&text);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&blocks).unwrap());
+                return;
+            }
+
+            let total: usize = blocks.iter().map(|block| block.tokens).sum();
+            println!(
+                "{:>11} {:<10} {:>8} {:>7}  Section",
+                "Lines", "Kind", "Tokens", "Share"
+            );
+            for block in &blocks {
+                println!(
+                    "{:>11} {:<10} {:>8} {:>6.2}%  {}",
+                    format!("{}-{}", block.start_line, block.end_line),
+                    block.kind.name(),
+                    block.tokens,
+                    block.tokens as f64 * 100.0 / total.max(1) as f64,
+                    block.section.as_deref().unwrap_or(""),
+                );
+            }
+            println!("{:>11} {:<10} {:>8}", "", "total", total);
+        }
+        Command::Html {
+            file,
+            method,
+            params,
+            profile,
+            json,
+        } => {
+            let bundle = resolve_bundle(params.as_deref(), profile.as_deref());
+            let html = read_text(file.as_deref(), input, "html");
+            let estimate = HtmlEstimator::from_bundle(&bundle, method).estimate(&html);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&estimate).unwrap());
+                return;
+            }
+
+            println!("Raw:      {:>8}", estimate.raw_tokens);
+            println!(
+                "Markup:   {:>8} ({:.2}%)",
+                estimate.markup_tokens,
+                estimate.markup_share() * 100.0
+            );
+            println!("Text:     {:>8}", estimate.text_tokens);
+            println!("Stripped: {:>8}", estimate.stripped_tokens);
+        }
+        Command::Split {
+            file,
+            output,
+            max_tokens,
+            overlap,
+            margin,
+            method,
+            params,
+            profile,
+        } => {
+            let bundle = resolve_bundle(params.as_deref(), profile.as_deref());
+            let text = read_text(file.as_deref(), input, "split");
+            let estimator = bundle.text_estimator(method);
+            let options = ChunkOptions {
+                max_tokens,
+                overlap,
+                margin,
+            };
+            let chunks = chunk_with(estimator.as_ref(), &text, &options);
+
+            fs::create_dir_all(&output).unwrap_or_else(|e| {
+                eprintln!("Error creating {}: {e}", output.display());
+                std::process::exit(1);
+            });
+            for (i, chunk) in chunks.iter().enumerate() {
+                let path = output.join(format!("chunk-{:04}.txt", i + 1));
+                fs::write(&path, chunk).unwrap_or_else(|e| {
+                    eprintln!("Error writing {}: {e}", path.display());
+                    std::process::exit(1);
+                });
+                println!("{:>8}  {}", estimator.estimate_text(chunk), path.display());
+            }
+            eprintln!("Wrote {} chunks to {}", chunks.len(), output.display());
+        }
+    }
+}
+
+fn parse_quantile(s: &str) -> Result<f64, String> {
+    let q: f64 = s.parse().map_err(|e| format!("{e}"))?;
+    if q > 0.0 && q < 1.0 {
+        Ok(q)
+    } else {
+        Err("quantile must be between 0 and 1".to_string())
+    }
+}
+
+fn parse_under_penalty(s: &str) -> Result<f64, String> {
+    let penalty: f64 = s.parse().map_err(|e| format!("{e}"))?;
+    if penalty.is_finite() && penalty >= 1.0 {
+        Ok(penalty)
     } else {
-        let mut buf = String::new();
-        io::stdin().read_to_string(&mut buf).unwrap();
-        buf
+        Err("under penalty must be a finite number of at least 1".to_string())
+    }
+}
+
+fn parse_max_tokens(s: &str) -> Result<usize, String> {
+    match s.parse() {
+        Ok(0) => Err("max tokens must be at least 1".to_string()),
+        Ok(max_tokens) => Ok(max_tokens),
+        Err(e) => Err(format!("{e}")),
+    }
+}
+
+fn resolve_bundle(params: Option<&Path>, profile: Option<&str>) -> ParameterBundle {
+    let bundle = match params {
+        Some(path) => load_bundle(path),
+        None => ParameterBundle::default(),
     };
+    match profile {
+        Some(name) => bundle.profile(name).unwrap_or_else(|| {
+            eprintln!("Unknown profile: {name}");
+            std::process::exit(1);
+        }),
+        None => bundle,
+    }
+}
 
-    if text.is_empty() {
-        eprintln!("No text provided");
+/// Text of the file or stdin, and the number of invalid bytes counted as
+/// tokens under `--invalid bytes`
+fn read_input(file: Option<&Path>, input: Input) -> (String, usize) {
+    let bytes = if let Some(file) = file {
+        fs::read(file)
+    } else if atty::is(atty::Stream::Stdin) {
+        eprintln!("No input provided");
+        std::process::exit(1);
+    } else {
+        let mut buf = Vec::new();
+        io::stdin().read_to_end(&mut buf).map(|_| buf)
+    };
+    bytes
+        .and_then(|bytes| decode_bytes(&bytes, input.encoding, input.invalid))
+        .unwrap_or_else(|e| {
+            eprintln!("Error reading input: {e}");
+            std::process::exit(1);
+        })
+}
+
+/// Text of the file or stdin, for a subcommand that reports more than a
+/// single estimate and so has nowhere to add invalid bytes
+fn read_text(file: Option<&Path>, input: Input, command: &str) -> String {
+    if input.invalid == InvalidPolicy::Bytes {
+        eprintln!("{command} does not support --invalid bytes; use lossy, skip or error");
         std::process::exit(1);
     }
+    read_input(file, input).0
+}
 
-    let tokens = estimate_tokens(&text);
-    println!("{tokens}");
+fn load_samples(path: &Path) -> Vec<Sample> {
+    let samples = load_dataset(path).unwrap_or_else(|e| {
+        eprintln!("Error reading dataset: {e}");
+        std::process::exit(1);
+    });
+    if samples.is_empty() {
+        eprintln!("Dataset is empty");
+        std::process::exit(1);
+    }
+    samples
+}
+
+fn load_bundle(path: &Path) -> ParameterBundle {
+    ParameterBundle::load(path).unwrap_or_else(|e| {
+        eprintln!("Error reading params: {e}");
+        std::process::exit(1);
+    })
+}
+
+fn print_report(out: &mut impl Write, report: &EvalReport, json: bool) -> io::Result<()> {
+    if json {
+        return writeln!(out, "{}", serde_json::to_string_pretty(report).unwrap());
+    }
+
+    writeln!(out, "Method: {}", report.method)?;
+    writeln!(
+        out,
+        "{:<32} {:>8} {:>10} {:>8} {:>8} {:>8} {:>8} {:>8}",
+        "Group", "Samples", "RMSE", "MAPE", "Under", "p50", "p90", "p99"
+    )?;
+    print_metrics_row(out, "overall", &report.overall)?;
+    for (category, metrics) in &report.by_category {
+        print_metrics_row(out, &format!("category:{category}"), metrics)?;
+    }
+    for (language, metrics) in &report.by_language {
+        print_metrics_row(out, &format!("lang:{language}"), metrics)?;
+    }
+    Ok(())
+}
+
+fn print_metrics_row(out: &mut impl Write, group: &str, metrics: &Metrics) -> io::Result<()> {
+    writeln!(
+        out,
+        "{:<32} {:>8} {:>10.4} {:>7.2}% {:>7.2}% {:>7.2}% {:>7.2}% {:>7.2}%",
+        group,
+        metrics.samples,
+        metrics.rmse,
+        metrics.mape * 100.0,
+        metrics.underestimate_rate * 100.0,
+        metrics.p50 * 100.0,
+        metrics.p90 * 100.0,
+        metrics.p99 * 100.0,
+    )
 }
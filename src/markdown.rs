@@ -0,0 +1,244 @@
+use crate::methods::method::EstimationMethod;
+use crate::methods::method_code::{is_fence, CodeMethod};
+use crate::params::ParameterBundle;
+use crate::{Method, DEFAULT_METHOD};
+use serde::Serialize;
+use std::ops::Range;
+
+/// Kind of a top-level Markdown block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockKind {
+    Heading,
+    Paragraph,
+    List,
+    Table,
+    Code,
+}
+
+impl BlockKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BlockKind::Heading => "heading",
+            BlockKind::Paragraph => "paragraph",
+            BlockKind::List => "list",
+            BlockKind::Table => "table",
+            BlockKind::Code => "code",
+        }
+    }
+}
+
+/// Estimate of one Markdown block
+#[derive(Debug, Clone, Serialize)]
+pub struct MarkdownBlock {
+    pub kind: BlockKind,
+    /// Text of the nearest heading at or above the block
+    pub section: Option<String>,
+    /// First and last line of the block, 1-based and inclusive
+    pub start_line: usize,
+    pub end_line: usize,
+    pub tokens: usize,
+}
+
+/// Split Markdown into top-level blocks, as byte ranges of `text`. Blank lines
+/// between blocks are not part of any block
+pub fn parse_markdown(text: &str) -> Vec<(BlockKind, Range<usize>)> {
+    parse_blocks(text)
+        .into_iter()
+        .map(|block| (block.kind, block.range))
+        .collect()
+}
+
+/// A block found by `parse_blocks`
+struct ParsedBlock {
+    kind: BlockKind,
+    range: Range<usize>,
+    /// First and last line, 1-based and inclusive
+    lines: (usize, usize),
+}
+
+fn parse_blocks(text: &str) -> Vec<ParsedBlock> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        lines.push((offset, line));
+        offset += line.len();
+    }
+
+    let is_blank = |i: usize| lines[i].1.trim().is_empty();
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i].1;
+        if is_blank(i) {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        i += 1;
+        let kind = if is_fence(line) {
+            while i < lines.len() && !is_fence(lines[i].1) {
+                i += 1;
+            }
+            i = (i + 1).min(lines.len());
+            BlockKind::Code
+        } else if heading_text(line).is_some() {
+            BlockKind::Heading
+        } else if is_table_row(line) {
+            while i < lines.len() && is_table_row(lines[i].1) {
+                i += 1;
+            }
+            BlockKind::Table
+        } else if is_list_item(line) {
+            // Items, indented continuations and blank lines between items
+            while i < lines.len() {
+                let next = lines[i].1;
+                if is_blank(i) {
+                    let resumes = (i + 1 < lines.len())
+                        && (is_list_item(lines[i + 1].1) || is_indented(lines[i + 1].1));
+                    if !resumes {
+                        break;
+                    }
+                } else if !is_list_item(next) && !is_indented(next) && starts_block(next) {
+                    break;
+                }
+                i += 1;
+            }
+            BlockKind::List
+        } else {
+            while i < lines.len() && !is_blank(i) && !starts_block(lines[i].1) {
+                i += 1;
+            }
+            BlockKind::Paragraph
+        };
+
+        let (last_offset, last_line) = lines[i - 1];
+        blocks.push(ParsedBlock {
+            kind,
+            range: lines[start].0..last_offset + last_line.len(),
+            lines: (start + 1, i),
+        });
+    }
+    blocks
+}
+
+fn heading_text(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start();
+    let level = trimmed.bytes().take_while(|b| *b == b'#').count();
+    let rest = &trimmed[level..];
+    let separated = rest.is_empty() || rest.starts_with(char::is_whitespace);
+    ((1..=6).contains(&level) && separated).then(|| rest.trim())
+}
+
+fn is_table_row(line: &str) -> bool {
+    line.trim_start().starts_with('|')
+}
+
+fn is_list_item(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with("- ") || trimmed.starts_with("* ") || trimmed.starts_with("+ ") {
+        return true;
+    }
+    let digits = trimmed.bytes().take_while(u8::is_ascii_digit).count();
+    digits > 0 && (trimmed[digits..].starts_with(". ") || trimmed[digits..].starts_with(") "))
+}
+
+fn is_indented(line: &str) -> bool {
+    line.starts_with("  ") || line.starts_with('\t')
+}
+
+fn starts_block(line: &str) -> bool {
+    is_fence(line) || heading_text(line).is_some() || is_table_row(line) || is_list_item(line)
+}
+
+/// Estimates Markdown block by block: prose goes to a text method, fenced code
+/// and tables to the code method, whose operator runs fit pipes and rules
+pub struct MarkdownEstimator {
+    prose: Box<dyn Fn(&str) -> usize + Send + Sync>,
+    code: CodeMethod,
+}
+
+impl MarkdownEstimator {
+    /// Estimator using the compiled-in parameters and the default text method
+    pub fn new() -> Self {
+        Self::from_bundle(&ParameterBundle::default(), DEFAULT_METHOD)
+    }
+
+    pub fn from_bundle(bundle: &ParameterBundle, method: Method) -> Self {
+        let mut code = CodeMethod::new();
+        code.set_parameters(bundle.code.clone());
+        Self {
+            prose: bundle.estimator(method),
+            code,
+        }
+    }
+
+    /// Per-block estimates in document order
+    pub fn breakdown(&self, text: &str) -> Vec<MarkdownBlock> {
+        let mut section = None;
+        parse_blocks(text)
+            .into_iter()
+            .map(|ParsedBlock { kind, range, lines }| {
+                let block = &text[range];
+                if kind == BlockKind::Heading {
+                    section = heading_text(block).map(str::to_string);
+                }
+                let tokens = match kind {
+                    BlockKind::Code | BlockKind::Table => self.code.estimate(block),
+                    _ => (self.prose)(block),
+                };
+                MarkdownBlock {
+                    kind,
+                    section: section.clone(),
+                    start_line: lines.0,
+                    end_line: lines.1,
+                    tokens,
+                }
+            })
+            .collect()
+    }
+
+    pub fn estimate(&self, text: &str) -> usize {
+        self.breakdown(text).iter().map(|block| block.tokens).sum()
+    }
+}
+
+impl Default for MarkdownEstimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = "# Title\n\nIntro paragraph\nspanning two lines.\n\n- one\n- two\n  continued\n\n| a | b |\n|---|---|\n| 1 | 2 |\n\n## Code\n```rust\nfn main() {}\n```\n";
+
+    #[test]
+    fn test_parse_blocks() {
+        let kinds: Vec<BlockKind> = parse_markdown(DOC).into_iter().map(|(k, _)| k).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                BlockKind::Heading,
+                BlockKind::Paragraph,
+                BlockKind::List,
+                BlockKind::Table,
+                BlockKind::Heading,
+                BlockKind::Code,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_breakdown_sections_and_lines() {
+        let blocks = MarkdownEstimator::new().breakdown(DOC);
+        assert_eq!(blocks[1].section.as_deref(), Some("Title"));
+        assert_eq!((blocks[1].start_line, blocks[1].end_line), (3, 4));
+        assert_eq!(blocks[5].section.as_deref(), Some("Code"));
+        assert_eq!((blocks[5].start_line, blocks[5].end_line), (15, 17));
+        assert!(blocks.iter().all(|block| block.tokens > 0));
+    }
+}
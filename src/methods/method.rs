@@ -1,7 +1,45 @@
+use alloc::boxed::Box;
+use alloc::string::String;
 use serde::{Deserialize, Serialize};
-use std::error::Error;
-use std::fs;
-use std::path::Path;
+#[cfg(feature = "std")]
+use std::{error::Error, fs, path::Path};
+
+/// `f32::round`, which `core` lacks; `libm` stands in without `std`
+#[cfg(feature = "std")]
+pub(crate) fn round(x: f32) -> f32 {
+    x.round()
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) fn round(x: f32) -> f32 {
+    libm::roundf(x)
+}
+
+/// Running estimate of text fed piece by piece, so that a growing text is not
+/// re-counted from the start on every estimate
+pub trait Accumulator {
+    /// Append the next piece of text
+    fn push(&mut self, piece: &str);
+
+    /// Estimate of all text pushed so far
+    fn estimate(&self) -> usize;
+}
+
+/// Fallback accumulator that re-estimates the whole buffered text
+struct Buffered<'a, M> {
+    method: &'a M,
+    text: String,
+}
+
+impl<M: EstimationMethod> Accumulator for Buffered<'_, M> {
+    fn push(&mut self, piece: &str) {
+        self.text.push_str(piece);
+    }
+
+    fn estimate(&self) -> usize {
+        self.method.estimate(&self.text)
+    }
+}
 
 /// Trait for token estimation methods
 pub trait EstimationMethod {
@@ -17,6 +55,20 @@ pub trait EstimationMethod {
     /// Estimate token count using extracted features and parameters
     fn estimate(&self, text: &str) -> usize;
 
+    /// Start a running estimate. Anything detected once per text, like the
+    /// language, is taken from `sample`. The default re-estimates the buffered
+    /// text on every call; the built-in methods accumulate their features
+    fn accumulator(&self, sample: &str) -> Box<dyn Accumulator + '_>
+    where
+        Self: Sized,
+    {
+        let _ = sample;
+        Box::new(Buffered {
+            method: self,
+            text: String::new(),
+        })
+    }
+
     /// Get current parameters
     fn parameters(&self) -> Self::Parameters;
 
@@ -24,6 +76,7 @@ pub trait EstimationMethod {
     fn set_parameters(&mut self, params: Self::Parameters);
 
     /// Load parameters from TOML file
+    #[cfg(feature = "std")]
     fn load_parameters(&mut self, path: &Path) -> Result<(), Box<dyn Error>> {
         let content = fs::read_to_string(path)?;
         let params: Self::Parameters = toml::from_str(&content)?;
@@ -32,6 +85,7 @@ pub trait EstimationMethod {
     }
 
     /// Save parameters to TOML file
+    #[cfg(feature = "std")]
     fn save_parameters(&self, path: &Path) -> Result<(), Box<dyn Error>> {
         let params = self.parameters();
         let content = toml::to_string_pretty(&params)?;
@@ -39,3 +93,21 @@ pub trait EstimationMethod {
         Ok(())
     }
 }
+
+/// Object-safe view of a method with its parameters set, for code that picks
+/// the method at runtime
+pub trait TextEstimator {
+    fn estimate_text(&self, text: &str) -> usize;
+
+    fn text_accumulator(&self, sample: &str) -> Box<dyn Accumulator + '_>;
+}
+
+impl<M: EstimationMethod> TextEstimator for M {
+    fn estimate_text(&self, text: &str) -> usize {
+        EstimationMethod::estimate(self, text)
+    }
+
+    fn text_accumulator(&self, sample: &str) -> Box<dyn Accumulator + '_> {
+        EstimationMethod::accumulator(self, sample)
+    }
+}
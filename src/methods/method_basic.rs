@@ -1,4 +1,5 @@
-use super::method::EstimationMethod;
+use super::method::{round, Accumulator, EstimationMethod};
+use alloc::boxed::Box;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,12 +21,56 @@ pub struct BasicParameters {
 
 impl Default for BasicParameters {
     fn default() -> Self {
-        Self {
-            char_coef: 0.321_774_5,
-            word_coef: 0.070_228_82,
-            avg_word_length_coef: 0.509_098_2,
-            space_coef: -0.158_310_9,
-            intercept: 1.591_021,
+        // Generated from params/bundle.toml by build.rs
+        include!(concat!(env!("OUT_DIR"), "/params/basic.rs"))
+    }
+}
+
+/// Basic features of text fed in pieces; words may span pieces
+#[derive(Debug, Clone, Default)]
+pub(crate) struct BasicCounter {
+    char_count: usize,
+    space_count: usize,
+    word_count: usize,
+    word_chars: usize,
+    in_word: bool,
+}
+
+impl BasicCounter {
+    /// Features of the whole of `text`
+    pub(crate) fn count(text: &str) -> BasicFeatures {
+        let mut counter = Self::default();
+        counter.push(text);
+        counter.features()
+    }
+
+    pub(crate) fn push(&mut self, piece: &str) {
+        for c in piece.chars() {
+            self.char_count += 1;
+            if c.is_whitespace() {
+                self.space_count += 1;
+                self.in_word = false;
+            } else {
+                if !self.in_word {
+                    self.word_count += 1;
+                    self.in_word = true;
+                }
+                self.word_chars += 1;
+            }
+        }
+    }
+
+    pub(crate) fn features(&self) -> BasicFeatures {
+        let avg_word_length = if self.word_count > 0 {
+            self.word_chars as f32 / self.word_count as f32
+        } else {
+            0.0
+        };
+        BasicFeatures {
+            char_count: self.char_count,
+            word_count: self.word_count,
+            avg_word_length,
+            space_count: self.space_count,
         }
     }
 }
@@ -42,6 +87,18 @@ impl BasicMethod {
     }
 }
 
+impl BasicMethod {
+    fn estimate_features(&self, features: &BasicFeatures) -> usize {
+        let estimate = self.parameters.char_coef * features.char_count as f32
+            + self.parameters.word_coef * features.word_count as f32
+            + self.parameters.avg_word_length_coef * features.avg_word_length
+            + self.parameters.space_coef * features.space_count as f32
+            + self.parameters.intercept;
+
+        round(estimate).max(0.0) as usize
+    }
+}
+
 impl Default for BasicMethod {
     fn default() -> Self {
         Self::new()
@@ -53,35 +110,18 @@ impl EstimationMethod for BasicMethod {
     type Parameters = BasicParameters;
 
     fn count(&self, text: &str) -> Self::Features {
-        let char_count = text.chars().count();
-        let space_count = text.chars().filter(|c| c.is_whitespace()).count();
-        let words: Vec<&str> = text.split_whitespace().collect();
-        let word_count = words.len();
-
-        let avg_word_length = if word_count > 0 {
-            let total_word_chars: usize = words.iter().map(|w| w.chars().count()).sum();
-            total_word_chars as f32 / word_count as f32
-        } else {
-            0.0
-        };
-
-        BasicFeatures {
-            char_count,
-            word_count,
-            avg_word_length,
-            space_count,
-        }
+        BasicCounter::count(text)
     }
 
     fn estimate(&self, text: &str) -> usize {
-        let features = self.count(text);
-        let estimate = self.parameters.char_coef * features.char_count as f32
-            + self.parameters.word_coef * features.word_count as f32
-            + self.parameters.avg_word_length_coef * features.avg_word_length
-            + self.parameters.space_coef * features.space_count as f32
-            + self.parameters.intercept;
+        self.estimate_features(&self.count(text))
+    }
 
-        estimate.round().max(0.0) as usize
+    fn accumulator(&self, _sample: &str) -> Box<dyn Accumulator + '_> {
+        Box::new(BasicAccumulator {
+            method: self,
+            counter: BasicCounter::default(),
+        })
     }
 
     fn parameters(&self) -> Self::Parameters {
@@ -92,3 +132,35 @@ impl EstimationMethod for BasicMethod {
         self.parameters = params;
     }
 }
+
+struct BasicAccumulator<'a> {
+    method: &'a BasicMethod,
+    counter: BasicCounter,
+}
+
+impl Accumulator for BasicAccumulator<'_> {
+    fn push(&mut self, piece: &str) {
+        self.counter.push(piece);
+    }
+
+    fn estimate(&self) -> usize {
+        self.method.estimate_features(&self.counter.features())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accumulator_matches_estimate() {
+        // Pieces split inside words; also runs without `std`
+        let text = "Words of varying length, split at odd places  across pieces.";
+        let method = BasicMethod::new();
+        let mut accumulator = method.accumulator(text);
+        for piece in text.as_bytes().chunks(7) {
+            accumulator.push(core::str::from_utf8(piece).unwrap());
+        }
+        assert_eq!(accumulator.estimate(), method.estimate(text));
+    }
+}
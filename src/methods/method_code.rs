@@ -0,0 +1,634 @@
+use super::method::{Accumulator, EstimationMethod};
+use serde::{Deserialize, Serialize};
+use std::ops::AddAssign;
+
+/// Lexical features of source code
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CodeFeatures {
+    /// Identifier pieces after splitting on camelCase, snake_case and digits
+    pub identifier_parts: usize,
+    pub identifier_chars: usize,
+    pub number_chars: usize,
+    /// Runs of consecutive ASCII punctuation, e.g. `!=`, `);` or `=>`
+    pub operator_runs: usize,
+    pub operator_chars: usize,
+    /// Lines starting with indentation
+    pub indent_runs: usize,
+    pub newline_count: usize,
+    /// Non-ASCII characters, typically in comments and string literals
+    pub other_chars: usize,
+}
+
+impl AddAssign<&CodeFeatures> for CodeFeatures {
+    fn add_assign(&mut self, other: &CodeFeatures) {
+        self.identifier_parts += other.identifier_parts;
+        self.identifier_chars += other.identifier_chars;
+        self.number_chars += other.number_chars;
+        self.operator_runs += other.operator_runs;
+        self.operator_chars += other.operator_chars;
+        self.indent_runs += other.indent_runs;
+        self.newline_count += other.newline_count;
+        self.other_chars += other.other_chars;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeParameters {
+    pub part_coef: f32,
+    pub identifier_char_coef: f32,
+    pub number_coef: f32,
+    pub operator_run_coef: f32,
+    pub operator_char_coef: f32,
+    pub indent_coef: f32,
+    pub newline_coef: f32,
+    pub other_coef: f32,
+    pub intercept: f32,
+}
+
+impl Default for CodeParameters {
+    fn default() -> Self {
+        // Generated from params/bundle.toml by build.rs
+        include!(concat!(env!("OUT_DIR"), "/params/code.rs"))
+    }
+}
+
+pub struct CodeMethod {
+    parameters: CodeParameters,
+}
+
+impl CodeMethod {
+    pub fn new() -> Self {
+        Self {
+            parameters: CodeParameters::default(),
+        }
+    }
+}
+
+impl Default for CodeMethod {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EstimationMethod for CodeMethod {
+    type Features = CodeFeatures;
+    type Parameters = CodeParameters;
+
+    fn count(&self, text: &str) -> Self::Features {
+        let mut features = CodeFeatures::default();
+        for line in text.split_inclusive('\n') {
+            if line.ends_with('\n') {
+                features.newline_count += 1;
+            }
+            let body = line.trim_end_matches(['\n', '\r']);
+            let trimmed = body.trim_start();
+            if !trimmed.is_empty() && trimmed.len() < body.len() {
+                features.indent_runs += 1;
+            }
+            scan_line(trimmed, &mut features);
+        }
+        features
+    }
+
+    fn estimate(&self, text: &str) -> usize {
+        if text.trim().is_empty() {
+            return 0;
+        }
+        self.estimate_features(&self.count(text))
+    }
+
+    fn accumulator(&self, _sample: &str) -> Box<dyn Accumulator + '_> {
+        Box::new(CodeAccumulator {
+            method: self,
+            committed: CodeFeatures::default(),
+            pending: String::new(),
+            scanner: TokenScanner::default(),
+            has_content: false,
+        })
+    }
+
+    fn parameters(&self) -> Self::Parameters {
+        self.parameters.clone()
+    }
+
+    fn set_parameters(&mut self, params: Self::Parameters) {
+        self.parameters = params;
+    }
+}
+
+impl CodeMethod {
+    fn estimate_features(&self, features: &CodeFeatures) -> usize {
+        let params = &self.parameters;
+        let estimate = params.part_coef * features.identifier_parts as f32
+            + params.identifier_char_coef * features.identifier_chars as f32
+            + params.number_coef * features.number_chars as f32
+            + params.operator_run_coef * features.operator_runs as f32
+            + params.operator_char_coef * features.operator_chars as f32
+            + params.indent_coef * features.indent_runs as f32
+            + params.newline_coef * features.newline_count as f32
+            + params.other_coef * features.other_chars as f32
+            + params.intercept;
+
+        estimate.round().max(0.0) as usize
+    }
+}
+
+fn scan_line(line: &str, features: &mut CodeFeatures) {
+    let bytes = line.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        if b.is_ascii_alphabetic() || b == b'_' {
+            let len = bytes[i..]
+                .iter()
+                .take_while(|b| b.is_ascii_alphanumeric() || **b == b'_')
+                .count();
+            features.identifier_chars += len;
+            features.identifier_parts += identifier_parts(&line[i..i + len]);
+            i += len;
+        } else if b.is_ascii_digit() {
+            let len = bytes[i..]
+                .iter()
+                .take_while(|b| b.is_ascii_alphanumeric() || **b == b'.')
+                .count();
+            features.number_chars += len;
+            i += len;
+        } else if b.is_ascii_punctuation() {
+            let len = bytes[i..]
+                .iter()
+                .take_while(|b| b.is_ascii_punctuation() && **b != b'_')
+                .count();
+            features.operator_runs += 1;
+            features.operator_chars += len;
+            i += len;
+        } else if b.is_ascii() {
+            // Spaces between tokens merge into the following token
+            i += 1;
+        } else {
+            features.other_chars += 1;
+            i += line[i..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+}
+
+/// Number of pieces in an identifier: `getHTTPResponse_code2` is get, HTTP,
+/// Response, code, 2
+fn identifier_parts(word: &str) -> usize {
+    let chars: Vec<char> = word.chars().collect();
+    let mut parts = 0;
+    let mut prev: Option<char> = None;
+    for (i, &c) in chars.iter().enumerate() {
+        if c == '_' {
+            prev = None;
+            continue;
+        }
+        let boundary = match prev {
+            None => true,
+            Some(p) => {
+                let next_lower = chars.get(i + 1).is_some_and(|n| n.is_ascii_lowercase());
+                (p.is_ascii_lowercase() && c.is_ascii_uppercase())
+                    || (p.is_ascii_uppercase() && c.is_ascii_uppercase() && next_lower)
+                    || p.is_ascii_digit() != c.is_ascii_digit()
+            }
+        };
+        if boundary {
+            parts += 1;
+        }
+        prev = Some(c);
+    }
+    parts
+}
+
+/// Keywords that start a line of code when an assignment, a call, a block or
+/// a trailing colon follows, so "let me know" stays prose
+const CODE_KEYWORDS: &[&str] = &[
+    "fn", "pub", "let", "const", "var", "def", "class", "import", "return", "function", "async",
+    "package", "func", "elif", "else", "try", "except", "if", "for", "while",
+];
+
+/// Prefixes that mark a line of code on their own
+const CODE_PREFIXES: &[&str] = &["#include", "#define", "//", "/*", "#!", "} "];
+
+fn is_code_line(line: &str) -> bool {
+    let trimmed = line.trim();
+    if trimmed.ends_with([';', '{', '}']) || CODE_PREFIXES.iter().any(|p| trimmed.starts_with(p)) {
+        return true;
+    }
+    let keyword = trimmed
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .next()
+        .unwrap_or_default();
+    let structured = trimmed[keyword.len()..].contains(['=', '(', '{']) || trimmed.ends_with(':');
+    let indented = trimmed.len() < line.trim_end().len();
+    structured && (indented || CODE_KEYWORDS.contains(&keyword))
+}
+
+/// Heuristic check whether text is source code rather than prose: it has at
+/// least two non-empty lines and at least half of them look like statements,
+/// declarations or block delimiters. A single line is never code
+pub fn is_code(text: &str) -> bool {
+    let mut lines = 0;
+    let mut code_lines = 0;
+    for line in text.lines().filter(|line| !line.trim().is_empty()) {
+        lines += 1;
+        if is_code_line(line) {
+            code_lines += 1;
+        }
+    }
+    lines >= 2 && code_lines * 2 >= lines
+}
+
+pub(crate) fn is_fence(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("```") || line.starts_with("~~~")
+}
+
+/// Split Markdown-style text into prose and fenced code segments, fences
+/// included in the code segment. An unclosed fence runs to the end of the text
+#[cfg(feature = "multilingual")]
+pub(crate) fn split_fenced(text: &str) -> Vec<(bool, &str)> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut in_code = false;
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+        if !is_fence(line) {
+            continue;
+        }
+        if in_code {
+            segments.push((true, &text[start..offset]));
+            start = offset;
+        } else {
+            if line_start > start {
+                segments.push((false, &text[start..line_start]));
+            }
+            start = line_start;
+        }
+        in_code = !in_code;
+    }
+    if start < text.len() {
+        segments.push((in_code, &text[start..]));
+    }
+    segments
+}
+
+/// Whether `estimate_with_code` sends any part of the text to the code method
+#[cfg(feature = "multilingual")]
+pub(crate) fn uses_code_path(text: &str) -> bool {
+    text.lines().any(is_fence) || is_code(text)
+}
+
+/// Estimate text with fenced blocks, or the whole text if it is code, going to
+/// the code method and the rest to `prose`. Language detection guesses arbitrary
+/// languages for source code, so the language-aware methods route it here
+#[cfg(feature = "multilingual")]
+pub(crate) fn estimate_with_code(
+    text: &str,
+    code: &CodeMethod,
+    prose: impl Fn(&str) -> usize,
+) -> usize {
+    if text.lines().any(is_fence) {
+        split_fenced(text)
+            .into_iter()
+            .map(|(is_code, segment)| {
+                if is_code {
+                    code.estimate(segment)
+                } else {
+                    prose(segment)
+                }
+            })
+            .sum()
+    } else if is_code(text) {
+        code.estimate(text)
+    } else {
+        prose(text)
+    }
+}
+
+/// Routes text between prose and code accumulators, segment by segment as
+/// `estimate_with_code` splits it, with a new `prose` accumulator for each prose
+/// segment. Text that is code as a whole goes to the code accumulator only
+#[cfg(feature = "multilingual")]
+pub(crate) fn routed_accumulator<'a>(
+    sample: &str,
+    code: &'a CodeMethod,
+    prose: impl Fn() -> Box<dyn Accumulator + 'a> + 'a,
+) -> Box<dyn Accumulator + 'a> {
+    if is_code(sample) && !sample.lines().any(is_fence) {
+        return code.accumulator(sample);
+    }
+    Box::new(Routed {
+        current: prose(),
+        prose: Box::new(prose),
+        code,
+        in_code: false,
+        done: 0,
+        line: String::new(),
+        decided: false,
+        closing: false,
+    })
+}
+
+/// Whether a line starting with `start` is a fence, or `None` while it could
+/// still become one
+#[cfg(feature = "multilingual")]
+fn fence_state(start: &str) -> Option<bool> {
+    let trimmed = start.trim_start();
+    if is_fence(trimmed) {
+        Some(true)
+    } else if trimmed.is_empty() || "```".starts_with(trimmed) || "~~~".starts_with(trimmed) {
+        None
+    } else {
+        Some(false)
+    }
+}
+
+#[cfg(feature = "multilingual")]
+struct Routed<'a> {
+    prose: Box<dyn Fn() -> Box<dyn Accumulator + 'a> + 'a>,
+    code: &'a CodeMethod,
+    /// Accumulator of the current segment
+    current: Box<dyn Accumulator + 'a>,
+    in_code: bool,
+    /// Estimates of the finished segments
+    done: usize,
+    /// Start of the current line while it could still be a fence. It is
+    /// counted once that is decided, at the latest at the end of the line
+    line: String,
+    decided: bool,
+    /// The current line closes a fence, so the code segment ends with it
+    closing: bool,
+}
+
+#[cfg(feature = "multilingual")]
+impl Routed<'_> {
+    fn start_segment(&mut self, in_code: bool) {
+        self.done += self.current.estimate();
+        self.current = if in_code {
+            self.code.accumulator("")
+        } else {
+            (self.prose)()
+        };
+        self.in_code = in_code;
+    }
+}
+
+#[cfg(feature = "multilingual")]
+impl Accumulator for Routed<'_> {
+    fn push(&mut self, piece: &str) {
+        for chunk in piece.split_inclusive('\n') {
+            let mut rest = chunk;
+            if !self.decided {
+                let body = rest.trim_end_matches(['\n']);
+                let mut fence = None;
+                for (i, c) in body.char_indices() {
+                    self.line.push(c);
+                    fence = fence_state(&self.line);
+                    if fence.is_some() {
+                        rest = &rest[i + c.len_utf8()..];
+                        break;
+                    }
+                }
+                if fence.is_none() {
+                    rest = &rest[body.len()..];
+                    // A complete line that was still undecided is not a fence
+                    fence = rest.ends_with('\n').then_some(false);
+                }
+                if let Some(fence) = fence {
+                    if fence && self.in_code {
+                        self.closing = true;
+                    } else if fence {
+                        self.start_segment(true);
+                    }
+                    self.current.push(&self.line);
+                    self.line.clear();
+                    self.decided = true;
+                }
+            }
+            if !rest.is_empty() {
+                self.current.push(rest);
+            }
+            if chunk.ends_with('\n') {
+                if self.closing {
+                    self.start_segment(false);
+                    self.closing = false;
+                }
+                self.decided = false;
+            }
+        }
+    }
+
+    fn estimate(&self) -> usize {
+        self.done + self.current.estimate()
+    }
+}
+
+/// Counts code a token at a time: pending text is counted and dropped at the
+/// last point where the features on both sides add up, so each push only scans
+/// the new text and each estimate only re-counts the token in progress
+struct CodeAccumulator<'a> {
+    method: &'a CodeMethod,
+    committed: CodeFeatures,
+    /// Text since the last cut
+    pending: String,
+    scanner: TokenScanner,
+    has_content: bool,
+}
+
+impl Accumulator for CodeAccumulator<'_> {
+    fn push(&mut self, piece: &str) {
+        self.has_content |= !piece.trim().is_empty();
+        let start = self.pending.len();
+        self.pending.push_str(piece);
+        let mut cut = None;
+        for (i, c) in piece.char_indices() {
+            if self.scanner.cuts_before(c) {
+                cut = Some(start + i);
+            }
+        }
+        if let Some(cut) = cut {
+            self.committed += &self.method.count(&self.pending[..cut]);
+            self.pending.drain(..cut);
+        }
+    }
+
+    fn estimate(&self) -> usize {
+        if !self.has_content {
+            return 0;
+        }
+        self.method.estimate_features(&self.features())
+    }
+}
+
+impl CodeAccumulator<'_> {
+    fn features(&self) -> CodeFeatures {
+        let mut features = self.method.count(&self.pending);
+        features += &self.committed;
+        features
+    }
+}
+
+/// Token `scan_line` is in the middle of
+#[derive(Debug, Clone, Copy, Default)]
+enum Token {
+    Identifier,
+    Number,
+    Operator,
+    /// A char that is a token of its own, or skipped
+    #[default]
+    Single,
+}
+
+/// Follows the tokens of `scan_line` char by char, to find where code can be
+/// counted in two parts
+#[derive(Debug, Clone, Default)]
+struct TokenScanner {
+    token: Token,
+    /// The current line has a non-whitespace char, so its indentation is
+    /// already decided
+    line_content: bool,
+    after_newline: bool,
+}
+
+impl TokenScanner {
+    /// Scan the next char and return whether the text before it counts the
+    /// same apart from the text after: at the start of a line, or where a token
+    /// starts after another one on the same line. Not before whitespace, which
+    /// would count as indentation
+    fn cuts_before(&mut self, c: char) -> bool {
+        let after_newline = std::mem::replace(&mut self.after_newline, c == '\n');
+        if after_newline {
+            self.line_content = false;
+        }
+        let continues = match self.token {
+            Token::Identifier => c.is_ascii_alphanumeric() || c == '_',
+            Token::Number => c.is_ascii_alphanumeric() || c == '.',
+            Token::Operator => c.is_ascii_punctuation() && c != '_',
+            Token::Single => false,
+        };
+        let cut = after_newline || (!continues && self.line_content && !c.is_whitespace());
+        if !continues {
+            self.token = if c.is_ascii_alphabetic() || c == '_' {
+                Token::Identifier
+            } else if c.is_ascii_digit() {
+                Token::Number
+            } else if c.is_ascii_punctuation() {
+                Token::Operator
+            } else {
+                Token::Single
+            };
+        }
+        self.line_content |= !c.is_whitespace();
+        cut
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identifier_parts() {
+        assert_eq!(identifier_parts("getHTTPResponse_code2"), 5);
+        assert_eq!(identifier_parts("MAX_SIZE"), 2);
+        assert_eq!(identifier_parts("x"), 1);
+    }
+
+    #[test]
+    fn test_accumulator_matches_estimate() {
+        let method = CodeMethod::new();
+        let text = "fn main() {\n    let total = compute_sum(1, 2);\n}\n";
+        let mut accumulator = method.accumulator(text);
+        for piece in text.split_inclusive(' ') {
+            accumulator.push(piece);
+        }
+        assert_eq!(accumulator.estimate(), method.estimate(text));
+
+        // Cut inside tokens, indentation and line endings
+        let text = "\tx_1 = getHTTPResponse(0.5e3)?;\r\n  \u{3000}// 日本語 コメント\n\
+            \u{3000}y=[a,b]  ;\n\n   \n}";
+        let chars: Vec<char> = text.chars().collect();
+        for size in 1..=8 {
+            let mut accumulator = CodeAccumulator {
+                method: &method,
+                committed: CodeFeatures::default(),
+                pending: String::new(),
+                scanner: TokenScanner::default(),
+                has_content: false,
+            };
+            for piece in chars.chunks(size) {
+                accumulator.push(&piece.iter().collect::<String>());
+            }
+            assert_eq!(
+                format!("{:?}", accumulator.features()),
+                format!("{:?}", method.count(text)),
+                "{size}"
+            );
+            // Only the last token is left to re-count
+            assert_eq!(accumulator.pending, "}");
+        }
+    }
+
+    #[test]
+    fn test_detect_code() {
+        assert!(is_code("fn main() {\n    println!(\"hi\");\n}"));
+        assert!(is_code("def hello():\n    print('hi')"));
+        assert!(!is_code("The quick brown fox jumps over the lazy dog."));
+        assert!(!is_code("Here is the list:\n- apples\n- pears"));
+        // Keywords alone do not make code, nor does a single line
+        for prose in [
+            "let me know if that works for you",
+            "return the package by friday",
+            "pub quiz tonight at eight",
+            "let me know if that works for you\nreturn the package by friday\n\
+             pub quiz tonight at eight",
+            "let total = compute_sum(1, 2);",
+        ] {
+            assert!(!is_code(prose), "{prose}");
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "multilingual")]
+    fn test_split_fenced() {
+        let text = "Intro\n```rust\nlet x = 1;\n```\nOutro";
+        let segments = split_fenced(text);
+        assert_eq!(
+            segments,
+            vec![
+                (false, "Intro\n"),
+                (true, "```rust\nlet x = 1;\n```\n"),
+                (false, "Outro")
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "multilingual")]
+    fn test_routed_matches_estimate_with_code() {
+        use crate::methods::method_simple::CharAccumulator;
+
+        let code = CodeMethod::new();
+        let prose = |text: &str| (text.chars().count() as f32 * 0.3).round() as usize;
+        let text = "Some prose before the code.\n  ```rust\nfn main() {}\n```\n\
+            More prose.\n``not a fence\n~~~\nlet x = 1;\n~~~";
+        for size in [1, 2, 5, 64] {
+            let mut accumulator = routed_accumulator(text, &code, || {
+                Box::new(CharAccumulator {
+                    coefficient: 0.3,
+                    char_count: 0,
+                })
+            });
+            for piece in text.as_bytes().chunks(size) {
+                accumulator.push(std::str::from_utf8(piece).unwrap());
+            }
+            assert_eq!(
+                accumulator.estimate(),
+                estimate_with_code(text, &code, prose),
+                "{size}"
+            );
+        }
+    }
+}
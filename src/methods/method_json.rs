@@ -0,0 +1,504 @@
+use super::method::{Accumulator, EstimationMethod};
+use crate::DefaultMethod;
+use serde::{Deserialize, Serialize};
+use std::ops::AddAssign;
+
+/// Lexical features of a JSON document. The scan does not require valid JSON,
+/// so truncated payloads are still counted
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JsonFeatures {
+    /// Structural characters: `{ } [ ] : ,`
+    pub punctuation_count: usize,
+    /// Strings, keys included; each carries a pair of quotes
+    pub string_count: usize,
+    /// Text estimate of object keys
+    pub key_tokens: usize,
+    /// Text estimate of string values
+    pub value_tokens: usize,
+    /// Characters of number literals
+    pub number_chars: usize,
+    /// `true`, `false` and `null`
+    pub literal_count: usize,
+    /// Whitespace runs containing a line break, i.e. pretty-printing indentation
+    pub newline_count: usize,
+    /// Characters that are not part of any JSON lexeme
+    pub other_chars: usize,
+}
+
+impl AddAssign<&JsonFeatures> for JsonFeatures {
+    fn add_assign(&mut self, other: &JsonFeatures) {
+        self.punctuation_count += other.punctuation_count;
+        self.string_count += other.string_count;
+        self.key_tokens += other.key_tokens;
+        self.value_tokens += other.value_tokens;
+        self.number_chars += other.number_chars;
+        self.literal_count += other.literal_count;
+        self.newline_count += other.newline_count;
+        self.other_chars += other.other_chars;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonParameters {
+    pub punctuation_coef: f32,
+    pub string_coef: f32,
+    pub key_coef: f32,
+    pub value_coef: f32,
+    pub number_coef: f32,
+    pub literal_coef: f32,
+    pub newline_coef: f32,
+    pub other_coef: f32,
+    pub intercept: f32,
+}
+
+impl Default for JsonParameters {
+    fn default() -> Self {
+        // Generated from params/bundle.toml by build.rs
+        include!(concat!(env!("OUT_DIR"), "/params/json.rs"))
+    }
+}
+
+/// Estimates JSON by its lexemes; keys and string values go through the
+/// default method, language-aware with the `multilingual` feature
+pub struct JsonMethod {
+    parameters: JsonParameters,
+    text: DefaultMethod,
+}
+
+impl JsonMethod {
+    pub fn new() -> Self {
+        Self {
+            parameters: JsonParameters::default(),
+            text: DefaultMethod::new(),
+        }
+    }
+
+    /// Set the parameters used to estimate keys and string values
+    pub fn set_text_parameters(&mut self, params: <DefaultMethod as EstimationMethod>::Parameters) {
+        self.text.set_parameters(params);
+    }
+
+    fn string_tokens(&self, content: &str) -> usize {
+        if content.is_empty() {
+            0
+        } else {
+            self.text.estimate(content).max(1)
+        }
+    }
+}
+
+impl Default for JsonMethod {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EstimationMethod for JsonMethod {
+    type Features = JsonFeatures;
+    type Parameters = JsonParameters;
+
+    fn count(&self, text: &str) -> Self::Features {
+        let bytes = text.as_bytes();
+        let mut features = JsonFeatures::default();
+        let mut i = 0;
+
+        while i < bytes.len() {
+            match bytes[i] {
+                b'{' | b'}' | b'[' | b']' | b':' | b',' => {
+                    features.punctuation_count += 1;
+                    i += 1;
+                }
+                b'"' => {
+                    let start = i + 1;
+                    let end = string_end(bytes, start);
+                    let content = &text[start..end];
+                    i = (end + 1).min(bytes.len());
+
+                    // A string followed by a colon is an object key
+                    let next = bytes[i..].iter().find(|b| !b.is_ascii_whitespace());
+                    let tokens = self.string_tokens(content);
+                    if next == Some(&b':') {
+                        features.key_tokens += tokens;
+                    } else {
+                        features.value_tokens += tokens;
+                    }
+                    features.string_count += 1;
+                }
+                b'-' | b'0'..=b'9' => {
+                    let len = bytes[i..].iter().take_while(|b| is_number_byte(b)).count();
+                    features.number_chars += len;
+                    i += len;
+                }
+                b if b.is_ascii_whitespace() => {
+                    let len = bytes[i..]
+                        .iter()
+                        .take_while(|b| b.is_ascii_whitespace())
+                        .count();
+                    if bytes[i..i + len].contains(&b'\n') {
+                        features.newline_count += 1;
+                    }
+                    i += len;
+                }
+                _ => {
+                    let rest = &text[i..];
+                    if let Some(literal) =
+                        LITERALS.iter().find(|literal| rest.starts_with(*literal))
+                    {
+                        features.literal_count += 1;
+                        i += literal.len();
+                    } else {
+                        features.other_chars += 1;
+                        i += rest.chars().next().map_or(1, char::len_utf8);
+                    }
+                }
+            }
+        }
+
+        features
+    }
+
+    fn estimate(&self, text: &str) -> usize {
+        if text.trim().is_empty() {
+            return 0;
+        }
+        self.estimate_features(&self.count(text))
+    }
+
+    fn accumulator(&self, _sample: &str) -> Box<dyn Accumulator + '_> {
+        Box::new(JsonAccumulator {
+            method: self,
+            features: JsonFeatures::default(),
+            lexeme: Lexeme::None,
+            key_candidate: None,
+            has_content: false,
+        })
+    }
+
+    fn parameters(&self) -> Self::Parameters {
+        self.parameters.clone()
+    }
+
+    fn set_parameters(&mut self, params: Self::Parameters) {
+        self.parameters = params;
+    }
+}
+
+impl JsonMethod {
+    fn estimate_features(&self, features: &JsonFeatures) -> usize {
+        let params = &self.parameters;
+        let estimate = params.punctuation_coef * features.punctuation_count as f32
+            + params.string_coef * features.string_count as f32
+            + params.key_coef * features.key_tokens as f32
+            + params.value_coef * features.value_tokens as f32
+            + params.number_coef * features.number_chars as f32
+            + params.literal_coef * features.literal_count as f32
+            + params.newline_coef * features.newline_count as f32
+            + params.other_coef * features.other_chars as f32
+            + params.intercept;
+
+        estimate.round().max(0.0) as usize
+    }
+}
+
+/// Strings longer than this are estimated with a running accumulator of the
+/// text method, which takes the language from this much of their start
+const LONG_STRING: usize = 1024;
+
+/// Literals outside strings, in the order `count` tries them
+const LITERALS: [&str; 3] = ["true", "false", "null"];
+
+/// Lexeme `JsonAccumulator` is in the middle of
+enum Lexeme<'a> {
+    None,
+    Number(usize),
+    Whitespace {
+        newline: bool,
+    },
+    /// Chars that are not part of any other lexeme, held while they could
+    /// still become a literal
+    Word(String),
+    Quoted {
+        content: String,
+        /// Takes over from `content` once the string is longer than `LONG_STRING`
+        long: Option<Box<dyn Accumulator + 'a>>,
+        escaped: bool,
+    },
+}
+
+/// Lexes JSON as `count` does, but incrementally: each push only scans the new
+/// text, and an estimate adds the lexeme in progress to the finished ones
+struct JsonAccumulator<'a> {
+    method: &'a JsonMethod,
+    /// Features of the finished lexemes
+    features: JsonFeatures,
+    lexeme: Lexeme<'a>,
+    /// Tokens of the last string, which is a key if a colon follows it
+    key_candidate: Option<usize>,
+    has_content: bool,
+}
+
+impl Accumulator for JsonAccumulator<'_> {
+    fn push(&mut self, piece: &str) {
+        self.has_content |= !piece.trim().is_empty();
+        let mut rest = piece;
+        while let Some(c) = rest.chars().next() {
+            if matches!(self.lexeme, Lexeme::Quoted { .. }) {
+                rest = self.push_string(rest);
+            } else {
+                self.push_char(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+
+    fn estimate(&self) -> usize {
+        if !self.has_content {
+            return 0;
+        }
+        // As if the input ended here: a pending string is a value
+        let mut features = self.features.clone();
+        if let Some(tokens) = self.key_candidate {
+            features.value_tokens += tokens;
+        }
+        match &self.lexeme {
+            Lexeme::None => {}
+            Lexeme::Number(len) => features.number_chars += len,
+            Lexeme::Whitespace { newline } => features.newline_count += usize::from(*newline),
+            Lexeme::Word(word) => count_word(&mut word.clone(), true, &mut features),
+            Lexeme::Quoted { content, long, .. } => {
+                features.string_count += 1;
+                features.value_tokens += self.method.string_tokens_of(content, long.as_deref());
+            }
+        }
+        self.method.estimate_features(&features)
+    }
+}
+
+impl JsonAccumulator<'_> {
+    /// Push string content up to the closing quote, and return the text after it
+    fn push_string<'t>(&mut self, text: &'t str) -> &'t str {
+        let Lexeme::Quoted {
+            content,
+            long,
+            escaped,
+        } = &mut self.lexeme
+        else {
+            return text;
+        };
+        let mut end = None;
+        for (i, b) in text.bytes().enumerate() {
+            match b {
+                _ if *escaped => *escaped = false,
+                b'\\' => *escaped = true,
+                b'"' => {
+                    end = Some(i);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let part = &text[..end.unwrap_or(text.len())];
+        match long {
+            Some(accumulator) => accumulator.push(part),
+            None => {
+                content.push_str(part);
+                if content.len() > LONG_STRING {
+                    let mut accumulator = self.method.text.accumulator(content);
+                    accumulator.push(content);
+                    content.clear();
+                    *long = Some(accumulator);
+                }
+            }
+        }
+        let Some(end) = end else {
+            return "";
+        };
+
+        let tokens = self.method.string_tokens_of(content, long.as_deref());
+        self.features.string_count += 1;
+        self.key_candidate = Some(tokens);
+        self.lexeme = Lexeme::None;
+        &text[end + 1..]
+    }
+
+    fn push_char(&mut self, c: char) {
+        match &mut self.lexeme {
+            Lexeme::Number(len) if c.is_ascii() && is_number_byte(&(c as u8)) => {
+                *len += 1;
+                return;
+            }
+            Lexeme::Whitespace { newline } if c.is_ascii_whitespace() => {
+                *newline |= c == '\n';
+                return;
+            }
+            Lexeme::Word(word) if is_word_char(c) => {
+                word.push(c);
+                count_word(word, false, &mut self.features);
+                return;
+            }
+            _ => {}
+        }
+        self.finish_lexeme();
+
+        if !c.is_ascii_whitespace() {
+            if let Some(tokens) = self.key_candidate.take() {
+                if c == ':' {
+                    self.features.key_tokens += tokens;
+                } else {
+                    self.features.value_tokens += tokens;
+                }
+            }
+        }
+        self.lexeme = match c {
+            '{' | '}' | '[' | ']' | ':' | ',' => {
+                self.features.punctuation_count += 1;
+                Lexeme::None
+            }
+            '"' => Lexeme::Quoted {
+                content: String::new(),
+                long: None,
+                escaped: false,
+            },
+            '-' | '0'..='9' => Lexeme::Number(1),
+            _ if c.is_ascii_whitespace() => Lexeme::Whitespace { newline: c == '\n' },
+            _ => {
+                let mut word = String::from(c);
+                count_word(&mut word, false, &mut self.features);
+                Lexeme::Word(word)
+            }
+        };
+    }
+
+    fn finish_lexeme(&mut self) {
+        match std::mem::replace(&mut self.lexeme, Lexeme::None) {
+            Lexeme::Number(len) => self.features.number_chars += len,
+            Lexeme::Whitespace { newline } => self.features.newline_count += usize::from(newline),
+            Lexeme::Word(mut word) => count_word(&mut word, true, &mut self.features),
+            Lexeme::None | Lexeme::Quoted { .. } => {}
+        }
+    }
+}
+
+impl JsonMethod {
+    fn string_tokens_of(&self, content: &str, long: Option<&dyn Accumulator>) -> usize {
+        match long {
+            Some(accumulator) => accumulator.estimate().max(1),
+            None => self.string_tokens(content),
+        }
+    }
+}
+
+/// Whether `count` handles `c` char by char, as a literal or another char
+fn is_word_char(c: char) -> bool {
+    !matches!(c, '{' | '}' | '[' | ']' | ':' | ',' | '"' | '-' | '0'..='9')
+        && !c.is_ascii_whitespace()
+}
+
+/// Count the literals and other chars at the start of `word`, and drain them.
+/// Unless the word is `finished`, a start that could still become a literal
+/// is kept
+fn count_word(word: &mut String, finished: bool, features: &mut JsonFeatures) {
+    while let Some(c) = word.chars().next() {
+        if let Some(literal) = LITERALS.iter().find(|literal| word.starts_with(*literal)) {
+            features.literal_count += 1;
+            word.drain(..literal.len());
+        } else if !finished
+            && LITERALS
+                .iter()
+                .any(|literal| literal.starts_with(word.as_str()))
+        {
+            return;
+        } else {
+            features.other_chars += 1;
+            word.drain(..c.len_utf8());
+        }
+    }
+}
+
+fn is_number_byte(b: &u8) -> bool {
+    matches!(b, b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E')
+}
+
+/// Index of the closing quote of a string starting at `start`, or the end of
+/// input for an unterminated string
+fn string_end(bytes: &[u8], start: usize) -> usize {
+    let mut i = start;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return i,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_json_lexemes() {
+        let method = JsonMethod::new();
+        let features = method.count(
+            "{\n  \"name\": \"Alice \\\"A\\\"\",\n  \"age\": 30.5,\n  \"tags\": [true, null]\n}",
+        );
+        assert_eq!(features.punctuation_count, 10);
+        assert_eq!(features.string_count, 4);
+        assert!(features.key_tokens >= 3);
+        assert!(features.value_tokens >= 1);
+        assert_eq!(features.number_chars, 4);
+        assert_eq!(features.literal_count, 2);
+        assert_eq!(features.newline_count, 4);
+        assert_eq!(features.other_chars, 0);
+    }
+
+    #[test]
+    fn test_truncated_json_is_counted() {
+        let method = JsonMethod::new();
+        assert_eq!(method.estimate("  "), 0);
+        assert!(method.estimate(r#"{"message": "unterminated"#) > 0);
+    }
+
+    #[test]
+    fn test_accumulator_matches_estimate() {
+        let method = JsonMethod::new();
+        let text = r#"{"users": [{"id": 1, "name": "Alice Smith"}, {"id": 2, "active": true}]}"#;
+        let mut accumulator = method.accumulator(text);
+        for piece in text.as_bytes().chunks(5) {
+            accumulator.push(std::str::from_utf8(piece).unwrap());
+        }
+        assert_eq!(accumulator.estimate(), method.estimate(text));
+
+        // Split inside strings, escapes, numbers and literals
+        let text = "{\"k\\\"ey\" :\n\t[-1.5e3, truex, nul, \"日本語\", fals]}\u{3000}\"end";
+        let chars: Vec<char> = text.chars().collect();
+        for size in [1, 2, 3, 5, 8] {
+            let mut accumulator = method.accumulator(text);
+            let mut expected = method.accumulator(text);
+            expected.push(text);
+            for (i, piece) in chars.chunks(size).enumerate() {
+                accumulator.push(&piece.iter().collect::<String>());
+                let pushed: String = chars[..(i * size + piece.len())].iter().collect();
+                assert_eq!(
+                    accumulator.estimate(),
+                    method.estimate(&pushed),
+                    "{size} {pushed}"
+                );
+            }
+            assert_eq!(accumulator.estimate(), expected.estimate(), "{size}");
+        }
+    }
+
+    #[test]
+    fn test_accumulator_long_string() {
+        let method = JsonMethod::new();
+        let value = "Plain English words in a long string value. ".repeat(200);
+        let text = format!("{{\"text\": \"{value}\", \"n\": 1}}");
+        let mut accumulator = method.accumulator(&text);
+        for piece in text.as_bytes().chunks(64) {
+            accumulator.push(std::str::from_utf8(piece).unwrap());
+        }
+        assert_eq!(accumulator.estimate(), method.estimate(&text));
+    }
+}
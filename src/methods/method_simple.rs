@@ -1,4 +1,5 @@
-use super::method::EstimationMethod;
+use super::method::{round, Accumulator, EstimationMethod};
+use alloc::boxed::Box;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -8,9 +9,8 @@ pub struct SimpleParameters {
 
 impl Default for SimpleParameters {
     fn default() -> Self {
-        Self {
-            coefficient: 0.329_268_3,
-        }
+        // Generated from params/bundle.toml by build.rs
+        include!(concat!(env!("OUT_DIR"), "/params/simple.rs"))
     }
 }
 
@@ -42,7 +42,14 @@ impl EstimationMethod for SimpleMethod {
 
     fn estimate(&self, text: &str) -> usize {
         let char_count = self.count(text);
-        (char_count as f32 * self.parameters.coefficient).round() as usize
+        round(char_count as f32 * self.parameters.coefficient) as usize
+    }
+
+    fn accumulator(&self, _sample: &str) -> Box<dyn Accumulator + '_> {
+        Box::new(CharAccumulator {
+            coefficient: self.parameters.coefficient,
+            char_count: 0,
+        })
     }
 
     fn parameters(&self) -> Self::Parameters {
@@ -53,3 +60,19 @@ impl EstimationMethod for SimpleMethod {
         self.parameters = params;
     }
 }
+
+/// Character count times a coefficient, for the simple methods
+pub(crate) struct CharAccumulator {
+    pub(crate) coefficient: f32,
+    pub(crate) char_count: usize,
+}
+
+impl Accumulator for CharAccumulator {
+    fn push(&mut self, piece: &str) {
+        self.char_count += piece.chars().count();
+    }
+
+    fn estimate(&self) -> usize {
+        round(self.char_count as f32 * self.coefficient) as usize
+    }
+}
@@ -0,0 +1,388 @@
+use crate::chat::ChatParameters;
+#[cfg(feature = "multilingual")]
+use crate::language::FixedLanguage;
+use crate::methods::method::{EstimationMethod, TextEstimator};
+use crate::methods::method_basic::{BasicMethod, BasicParameters};
+use crate::methods::method_code::{CodeMethod, CodeParameters};
+use crate::methods::method_json::{JsonMethod, JsonParameters};
+#[cfg(feature = "multilingual")]
+use crate::methods::method_multilingual::{MultilingualMethod, MultilingualMethodParameters};
+#[cfg(feature = "multilingual")]
+use crate::methods::method_multilingual_simple::{
+    MultilingualSimpleMethod, MultilingualSimpleMethodParameters,
+};
+use crate::methods::method_simple::{SimpleMethod, SimpleParameters};
+use crate::tools::ToolParameters;
+use crate::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::Path;
+use toml_edit::{DocumentMut, Item, Table, Value};
+
+/// Parameters for every estimation method, stored in a single TOML or JSON file.
+///
+/// Missing method sections fall back to the compiled-in defaults, which are
+/// generated from `params/bundle.toml` at build time.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParameterBundle {
+    pub simple: SimpleParameters,
+    pub basic: BasicParameters,
+    #[cfg(feature = "multilingual")]
+    pub multilingual: MultilingualMethodParameters,
+    #[cfg(feature = "multilingual")]
+    pub multilingual_simple: MultilingualSimpleMethodParameters,
+    pub json: JsonParameters,
+    pub code: CodeParameters,
+    pub chat: ChatParameters,
+    pub tools: ToolParameters,
+    /// Named profiles overriding some of the sections above
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, ParameterProfile>,
+}
+
+/// Per-profile overrides; sections left out inherit from the bundle
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParameterProfile {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub simple: Option<SimpleParameters>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub basic: Option<BasicParameters>,
+    #[cfg(feature = "multilingual")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub multilingual: Option<MultilingualMethodParameters>,
+    #[cfg(feature = "multilingual")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub multilingual_simple: Option<MultilingualSimpleMethodParameters>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub json: Option<JsonParameters>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<CodeParameters>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chat: Option<ChatParameters>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<ToolParameters>,
+}
+
+impl ParameterBundle {
+    /// Load a bundle from a `.json` or `.toml` file, chosen by extension
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let content = fs::read_to_string(path)?;
+        if is_json(path) {
+            Self::from_json_str(&content)
+        } else {
+            Self::from_toml_str(&content)
+        }
+    }
+
+    /// Save the bundle as `.json` or `.toml`, chosen by extension. An existing
+    /// TOML file is updated in place, so its comments survive
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let content = if is_json(path) {
+            serde_json::to_string_pretty(self)?
+        } else {
+            let updated = toml::to_string_pretty(self)?;
+            match fs::read_to_string(path) {
+                Ok(existing) => update_toml(&existing, &updated)?,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => updated,
+                Err(e) => return Err(e.into()),
+            }
+        };
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    pub fn from_toml_str(content: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(toml::from_str(content)?)
+    }
+
+    pub fn from_json_str(content: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(serde_json::from_str(content)?)
+    }
+
+    /// Resolve a named profile into a full bundle without further profiles
+    pub fn profile(&self, name: &str) -> Option<Self> {
+        let profile = self.profiles.get(name)?;
+        Some(Self {
+            simple: profile
+                .simple
+                .clone()
+                .unwrap_or_else(|| self.simple.clone()),
+            basic: profile.basic.clone().unwrap_or_else(|| self.basic.clone()),
+            #[cfg(feature = "multilingual")]
+            multilingual: profile
+                .multilingual
+                .clone()
+                .unwrap_or_else(|| self.multilingual.clone()),
+            #[cfg(feature = "multilingual")]
+            multilingual_simple: profile
+                .multilingual_simple
+                .clone()
+                .unwrap_or_else(|| self.multilingual_simple.clone()),
+            json: profile.json.clone().unwrap_or_else(|| self.json.clone()),
+            code: profile.code.clone().unwrap_or_else(|| self.code.clone()),
+            chat: profile.chat.clone().unwrap_or_else(|| self.chat.clone()),
+            tools: profile.tools.clone().unwrap_or_else(|| self.tools.clone()),
+            profiles: BTreeMap::new(),
+        })
+    }
+
+    /// Build an estimator for `method` using this bundle's parameters
+    pub fn estimator(&self, method: Method) -> Box<dyn Fn(&str) -> usize + Send + Sync> {
+        let estimator = self.text_estimator(method);
+        Box::new(move |text| estimator.estimate_text(text))
+    }
+
+    /// Build `method` with this bundle's parameters, accumulators included
+    pub fn text_estimator(&self, method: Method) -> Box<dyn TextEstimator + Send + Sync> {
+        match method {
+            Method::Simple => {
+                let mut estimator = SimpleMethod::new();
+                estimator.set_parameters(self.simple.clone());
+                Box::new(estimator)
+            }
+            Method::Basic => {
+                let mut estimator = BasicMethod::new();
+                estimator.set_parameters(self.basic.clone());
+                Box::new(estimator)
+            }
+            #[cfg(feature = "multilingual")]
+            Method::Multilingual => Box::new(self.multilingual_method()),
+            #[cfg(feature = "multilingual")]
+            Method::MultilingualSimple => Box::new(self.multilingual_simple_method()),
+            Method::Json => {
+                let mut estimator = JsonMethod::new();
+                estimator.set_parameters(self.json.clone());
+                estimator.set_text_parameters(self.text_parameters());
+                Box::new(estimator)
+            }
+            Method::Code => {
+                let mut estimator = CodeMethod::new();
+                estimator.set_parameters(self.code.clone());
+                Box::new(estimator)
+            }
+        }
+    }
+
+    /// Build a multilingual `method` that estimates all text as `language`,
+    /// any tag `normalize_language` accepts, instead of detecting it
+    #[cfg(feature = "multilingual")]
+    pub fn text_estimator_with_language(
+        &self,
+        method: Method,
+        language: &str,
+    ) -> Result<Box<dyn TextEstimator + Send + Sync>, String> {
+        let language = FixedLanguage::from_tag(language)?;
+        match method {
+            Method::Multilingual => {
+                let mut estimator = self.multilingual_method();
+                estimator.set_detector(language);
+                Ok(Box::new(estimator))
+            }
+            Method::MultilingualSimple => {
+                let mut estimator = self.multilingual_simple_method();
+                estimator.set_detector(language);
+                Ok(Box::new(estimator))
+            }
+            _ => Err(format!("the {method} method does not use a language")),
+        }
+    }
+
+    #[cfg(feature = "multilingual")]
+    fn multilingual_method(&self) -> MultilingualMethod {
+        let mut estimator = MultilingualMethod::new();
+        estimator.set_parameters(self.multilingual.clone());
+        estimator.set_code_parameters(self.code.clone());
+        estimator
+    }
+
+    #[cfg(feature = "multilingual")]
+    fn multilingual_simple_method(&self) -> MultilingualSimpleMethod {
+        let mut estimator = MultilingualSimpleMethod::new();
+        estimator.set_parameters(self.multilingual_simple.clone());
+        estimator.set_code_parameters(self.code.clone());
+        estimator
+    }
+
+    /// Parameters of `DEFAULT_METHOD`, which estimates JSON keys and values
+    #[cfg(feature = "multilingual")]
+    pub(crate) fn text_parameters(&self) -> MultilingualSimpleMethodParameters {
+        self.multilingual_simple.clone()
+    }
+
+    #[cfg(not(feature = "multilingual"))]
+    pub(crate) fn text_parameters(&self) -> SimpleParameters {
+        self.simple.clone()
+    }
+}
+
+/// Per-language maps, which a fit replaces whole: languages it dropped must
+/// not keep their old entries
+const REPLACED_TABLES: [&str; 2] = ["language_params", "language_samples"];
+
+/// `existing` TOML with the values of `updated`, keeping the comments and
+/// order of the keys in both. Keys missing from `updated` are kept, since a
+/// build without a feature does not serialize that feature's sections, except
+/// inside `REPLACED_TABLES`
+fn update_toml(existing: &str, updated: &str) -> Result<String, Box<dyn Error>> {
+    let mut document: DocumentMut = existing.parse()?;
+    let updated: DocumentMut = updated.parse()?;
+    update_table(document.as_table_mut(), updated.as_table(), false);
+    Ok(document.to_string())
+}
+
+/// Update `existing` from `updated`; with `replace`, keys missing from
+/// `updated` are removed
+fn update_table(existing: &mut Table, updated: &Table, replace: bool) {
+    if replace {
+        existing.retain(|key, _| updated.contains_key(key));
+    }
+    for (key, item) in updated.iter() {
+        let replace = replace || REPLACED_TABLES.contains(&key);
+        match (existing.get_mut(key), item) {
+            (Some(Item::Table(old)), Item::Table(new)) => update_table(old, new, replace),
+            // Parameters are f32, so unchanged ones read back with float noise
+            (Some(Item::Value(old)), Item::Value(new)) if same_f32(old, new) => {}
+            (Some(Item::Value(old)), Item::Value(new)) => {
+                let decor = old.decor().clone();
+                *old = new.clone();
+                *old.decor_mut() = decor;
+            }
+            _ => {
+                existing.insert(key, item.clone());
+            }
+        }
+    }
+}
+
+fn same_f32(a: &Value, b: &Value) -> bool {
+    let number = |value: &Value| match value {
+        Value::Float(f) => Some(*f.value() as f32),
+        Value::Integer(i) => Some(*i.value() as f32),
+        _ => None,
+    };
+    number(a).is_some_and(|a| Some(a) == number(b))
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundle_file_matches_defaults() {
+        let bundle = ParameterBundle::load(Path::new("params/bundle.toml")).unwrap();
+        let defaults = ParameterBundle::default();
+        assert_eq!(bundle.simple.coefficient, defaults.simple.coefficient);
+        #[cfg(feature = "multilingual")]
+        assert_eq!(
+            bundle.multilingual.language_params.len(),
+            defaults.multilingual.language_params.len()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "multilingual")]
+    fn test_estimator_with_language() {
+        let bundle = ParameterBundle::default();
+        let text = "Plain English prose, estimated as Japanese.";
+        let estimator = bundle
+            .text_estimator_with_language(Method::Multilingual, "ja-JP")
+            .unwrap();
+        assert_eq!(
+            estimator.estimate_text(text),
+            MultilingualMethod::new()
+                .estimate_with_language(text, "jpn")
+                .unwrap()
+        );
+        assert!(bundle
+            .text_estimator_with_language(Method::MultilingualSimple, "klingon")
+            .is_err());
+        assert!(bundle
+            .text_estimator_with_language(Method::Basic, "ja")
+            .is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "multilingual")]
+    fn test_language_params_are_not_overfit() {
+        // Coefficients this large come from too few samples of a language
+        let bundle = ParameterBundle::default();
+        for (lang, params) in &bundle.multilingual.language_params {
+            let coefs = [params.word_coef, params.space_coef, params.intercept];
+            assert!(coefs.iter().all(|c| c.abs() <= 10.0), "{lang}");
+        }
+    }
+
+    #[test]
+    fn test_save_keeps_comments() {
+        let existing =
+            "# Hand-set\n[json]\n# Keys\nkey_coef = 1.0 # inline\nother = 2\nsame = 0.7\n\n[unserialized]\na = 1\n";
+        let updated = "[json]\nkey_coef = 0.5\nother = 2.0\nsame = 0.699999988079071\nnew = 3\n";
+        let merged = update_toml(existing, updated).unwrap();
+        assert!(merged.starts_with("# Hand-set\n[json]\n# Keys\nkey_coef = 0.5 # inline\n"));
+        assert!(merged.contains("other = 2\nsame = 0.7\n"));
+        assert!(merged.contains("new = 3") && merged.contains("[unserialized]\na = 1\n"));
+
+        let bundle = fs::read_to_string("params/bundle.toml").unwrap();
+        let defaults = toml::to_string_pretty(&ParameterBundle::default()).unwrap();
+        let merged = update_toml(&bundle, &defaults).unwrap();
+        for comment in bundle.lines().filter(|line| line.starts_with('#')) {
+            assert!(merged.contains(comment), "{comment}");
+        }
+        let merged = ParameterBundle::from_toml_str(&merged).unwrap();
+        assert_eq!(
+            merged.simple.coefficient,
+            ParameterBundle::default().simple.coefficient
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "multilingual")]
+    fn test_save_replaces_language_tables() {
+        let bundle = fs::read_to_string("params/bundle.toml").unwrap();
+        assert!(bundle.contains("[multilingual.language_params.ita]"));
+        let mut fitted = ParameterBundle::default();
+        fitted
+            .multilingual
+            .language_params
+            .retain(|lang, _| lang == "eng");
+        fitted.multilingual.language_samples = [("eng".to_string(), 18)].into();
+
+        let updated = toml::to_string_pretty(&fitted).unwrap();
+        let merged = update_toml(&bundle, &updated).unwrap();
+        assert!(!merged.contains("[multilingual.language_params.ita]"));
+        let merged = ParameterBundle::from_toml_str(&merged).unwrap();
+        let languages: Vec<&String> = merged.multilingual.language_params.keys().collect();
+        assert_eq!(languages, ["eng"]);
+        assert_eq!(merged.multilingual.language_samples.len(), 1);
+        // The other method keeps its own languages
+        assert_eq!(
+            merged.multilingual_simple.language_params.len(),
+            ParameterBundle::default()
+                .multilingual_simple
+                .language_params
+                .len()
+        );
+    }
+
+    #[test]
+    fn test_profile_overrides_section() {
+        let bundle = ParameterBundle::from_json_str(
+            r#"{"profiles": {"o200k": {"simple": {"coefficient": 0.25}}}}"#,
+        )
+        .unwrap();
+        let profile = bundle.profile("o200k").unwrap();
+        assert_eq!(profile.simple.coefficient, 0.25);
+        assert_eq!(profile.basic.intercept, bundle.basic.intercept);
+        assert!(bundle.profile("missing").is_none());
+    }
+}
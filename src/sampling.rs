@@ -0,0 +1,256 @@
+use crate::decode::{Decoded, Decoder, Encoding, InvalidPolicy};
+use crate::methods::method::TextEstimator;
+use serde::Serialize;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::ops::Range;
+
+/// Where `estimate_sampled_with` looks in the text
+#[derive(Debug, Clone)]
+pub struct SampleOptions {
+    /// Number of windows, one in each of as many equal strata; at least 1
+    pub windows: usize,
+    /// Bytes per window; at least 1
+    pub window_bytes: usize,
+}
+
+impl Default for SampleOptions {
+    fn default() -> Self {
+        Self {
+            windows: 32,
+            window_bytes: 4096,
+        }
+    }
+}
+
+/// Token estimate extrapolated from sampled windows
+#[derive(Debug, Clone, Serialize)]
+pub struct SampledEstimate {
+    pub tokens: usize,
+    /// Standard error of `tokens` from the spread between windows; 0 when the
+    /// whole text was estimated
+    pub std_error: f64,
+    pub sampled_bytes: usize,
+    pub total_bytes: usize,
+}
+
+impl SampledEstimate {
+    /// Standard error relative to the estimate
+    pub fn relative_error(&self) -> f64 {
+        self.std_error / self.tokens.max(1) as f64
+    }
+}
+
+/// One window in the middle of each of `windows` equal strata of `len` bytes
+fn window_ranges(len: usize, options: &SampleOptions) -> Vec<Range<usize>> {
+    let windows = options.windows.max(1);
+    (0..windows)
+        .map(|i| {
+            let stratum = i * len / windows..(i + 1) * len / windows;
+            let width = options.window_bytes.max(1).min(stratum.len());
+            let start = stratum.start + (stratum.len() - width) / 2;
+            start..start + width
+        })
+        .collect()
+}
+
+/// Estimate a large text from windows spread evenly across it, extrapolating
+/// each window's tokens per byte. Texts no longer than the windows together
+/// are estimated in full
+pub fn estimate_sampled_with<M: TextEstimator + ?Sized>(
+    method: &M,
+    text: &str,
+    options: &SampleOptions,
+) -> SampledEstimate {
+    if text.len() <= options.windows.max(1) * options.window_bytes.max(1) {
+        return SampledEstimate {
+            tokens: method.estimate_text(text),
+            std_error: 0.0,
+            sampled_bytes: text.len(),
+            total_bytes: text.len(),
+        };
+    }
+    let windows = window_ranges(text.len(), options).into_iter().map(|range| {
+        let mut start = range.start;
+        while !text.is_char_boundary(start) {
+            start += 1;
+        }
+        let mut end = range.end.max(start);
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        &text[start..end]
+    });
+    let samples = windows
+        .filter(|window| !window.is_empty())
+        .map(|window| (window.len(), method.estimate_text(window)))
+        .collect();
+    extrapolate(samples, text.len())
+}
+
+/// Like `estimate_sampled_with`, reading only the windows of a seekable source
+/// such as a UTF-8 file. Windows are cut to whole UTF-8 sequences, and invalid
+/// bytes in them are handled as `invalid` says; under `InvalidPolicy::Error`
+/// only the windows read are checked
+pub fn estimate_sampled_reader_with<M: TextEstimator + ?Sized>(
+    method: &M,
+    mut reader: impl Read + Seek,
+    options: &SampleOptions,
+    invalid: InvalidPolicy,
+) -> io::Result<SampledEstimate> {
+    let len = reader.seek(SeekFrom::End(0))? as usize;
+    if len <= options.windows.max(1) * options.window_bytes.max(1) {
+        reader.seek(SeekFrom::Start(0))?;
+        let mut bytes = Vec::with_capacity(len);
+        reader.read_to_end(&mut bytes)?;
+        return Ok(SampledEstimate {
+            tokens: estimate_window(method, &bytes, invalid)?,
+            std_error: 0.0,
+            sampled_bytes: len,
+            total_bytes: len,
+        });
+    }
+
+    let mut samples = Vec::new();
+    for range in window_ranges(len, options) {
+        reader.seek(SeekFrom::Start(range.start as u64))?;
+        let mut bytes = vec![0; range.len()];
+        reader.read_exact(&mut bytes)?;
+        let bytes = whole_sequences(&bytes);
+        if !bytes.is_empty() {
+            samples.push((bytes.len(), estimate_window(method, bytes, invalid)?));
+        }
+    }
+    Ok(extrapolate(samples, len))
+}
+
+/// Trim a window cut out of UTF-8 to the sequences wholly inside it
+fn whole_sequences(bytes: &[u8]) -> &[u8] {
+    let start = bytes
+        .iter()
+        .take(3)
+        .take_while(|b| (**b & 0xC0) == 0x80)
+        .count();
+    let bytes = &bytes[start..];
+    let end = match std::str::from_utf8(bytes) {
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        _ => bytes.len(),
+    };
+    &bytes[..end]
+}
+
+/// Tokens in UTF-8 `bytes`, counting invalid bytes as `StreamingEstimator` does
+fn estimate_window<M: TextEstimator + ?Sized>(
+    method: &M,
+    bytes: &[u8],
+    invalid: InvalidPolicy,
+) -> io::Result<usize> {
+    let mut decoder = Decoder::new(Encoding::Utf8, invalid);
+    let mut text = String::with_capacity(bytes.len());
+    let mut invalid_bytes = 0;
+    let mut sink = |piece: Decoded<'_>| match piece {
+        Decoded::Text(piece) => text.push_str(piece),
+        Decoded::Invalid(len) => invalid_bytes += len,
+    };
+    decoder.push(bytes, &mut sink)?;
+    decoder.finish(&mut sink)?;
+    Ok(method.estimate_text(&text) + invalid_bytes)
+}
+
+/// Scale the tokens per byte of `samples`, as (bytes, tokens) per window, up
+/// to `total_bytes`
+fn extrapolate(samples: Vec<(usize, usize)>, total_bytes: usize) -> SampledEstimate {
+    let sampled_bytes: usize = samples.iter().map(|(bytes, _)| bytes).sum();
+    let sampled_tokens: usize = samples.iter().map(|(_, tokens)| tokens).sum();
+    let rate = sampled_tokens as f64 / sampled_bytes.max(1) as f64;
+
+    // Strata are equal, so the spread of per-window rates gives the error,
+    // shrunk by the share of the text that was actually read
+    let k = samples.len() as f64;
+    let variance = samples
+        .iter()
+        .map(|&(bytes, tokens)| (tokens as f64 / bytes as f64 - rate).powi(2))
+        .sum::<f64>()
+        / (k - 1.0).max(1.0);
+    let coverage = (sampled_bytes as f64 / total_bytes as f64).min(1.0);
+    let std_error = (variance / k.max(1.0) * (1.0 - coverage)).sqrt() * total_bytes as f64;
+
+    SampledEstimate {
+        tokens: (rate * total_bytes as f64).round() as usize,
+        std_error,
+        sampled_bytes,
+        total_bytes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::methods::method::EstimationMethod;
+    use crate::DefaultMethod;
+
+    #[test]
+    fn test_small_text_is_exact() {
+        let method = DefaultMethod::new();
+        let text = "Short enough to estimate in full.";
+        let estimate = estimate_sampled_with(&method, text, &SampleOptions::default());
+        assert_eq!(estimate.tokens, method.estimate(text));
+        assert_eq!(estimate.std_error, 0.0);
+    }
+
+    #[test]
+    fn test_empty_windows_read_a_byte() {
+        let method = DefaultMethod::new();
+        let text = "Zero-byte windows would sample nothing at all. ".repeat(100);
+        let options = SampleOptions {
+            windows: 4,
+            window_bytes: 0,
+        };
+        let estimate = estimate_sampled_with(&method, &text, &options);
+        assert_eq!(estimate.sampled_bytes, 4);
+    }
+
+    #[test]
+    fn test_sampled_estimate_is_close() {
+        let method = DefaultMethod::new();
+        let text = "English prose with words of varying length. 日本語の文も混ざっています。\n"
+            .repeat(20_000);
+        let options = SampleOptions::default();
+        let full = method.estimate(&text) as f64;
+
+        let estimate = estimate_sampled_with(&method, &text, &options);
+        assert!(estimate.sampled_bytes <= options.windows * options.window_bytes);
+        assert!((estimate.tokens as f64 - full).abs() / full < 0.05);
+
+        let read = estimate_sampled_reader_with(
+            &method,
+            io::Cursor::new(&text),
+            &options,
+            InvalidPolicy::Lossy,
+        )
+        .unwrap();
+        assert_eq!(read.total_bytes, text.len());
+        assert!((read.tokens as f64 - full).abs() / full < 0.05);
+    }
+
+    #[test]
+    fn test_reader_honours_invalid_policy() {
+        let method = DefaultMethod::new();
+        let mut bytes = b"valid text \xFF\xFE ".repeat(20_000);
+        let options = SampleOptions::default();
+        let sample = |bytes: &[u8], invalid| {
+            estimate_sampled_reader_with(&method, io::Cursor::new(bytes), &options, invalid)
+        };
+
+        let skip = sample(&bytes, InvalidPolicy::Skip).unwrap();
+        let counted = sample(&bytes, InvalidPolicy::Bytes).unwrap();
+        assert!(counted.tokens > skip.tokens);
+        let err = sample(&bytes, InvalidPolicy::Error).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // Small input is read whole
+        bytes.truncate(100);
+        let whole = sample(&bytes, InvalidPolicy::Bytes).unwrap();
+        assert_eq!(whole.std_error, 0.0);
+        assert!(sample(&bytes, InvalidPolicy::Error).is_err());
+    }
+}
@@ -0,0 +1,251 @@
+use crate::decode::{Decoded, Decoder, Encoding, InvalidPolicy};
+use crate::methods::method::{Accumulator, TextEstimator};
+use crate::DefaultMethod;
+use std::io::{self, Read};
+use std::sync::OnceLock;
+
+/// Text kept from the start of the stream to detect its language
+pub const DETECTION_WINDOW: usize = 1024;
+
+/// Size of the buffer `read_from` reads into
+const READ_BUFFER: usize = 64 * 1024;
+
+/// Running estimate of text that keeps growing, e.g. streamed LLM output.
+/// Each push costs time proportional to the pushed chunk, not to everything
+/// pushed so far.
+///
+/// This approximates `estimate_text`: the language, and whether the text is
+/// code as a whole, are decided from the first `DETECTION_WINDOW` bytes and
+/// kept for the rest of the stream. Fenced code blocks are still routed as
+/// they arrive, so the estimates only differ if the language changes, or the
+/// text turns into unfenced code, after the window
+pub struct StreamingEstimator<'m, M: TextEstimator + ?Sized = DefaultMethod> {
+    method: &'m M,
+    /// Start of the stream; the accumulator is rebuilt from it until it is full
+    window: String,
+    window_full: bool,
+    accumulator: Box<dyn Accumulator + 'm>,
+    decoder: Decoder,
+    invalid_bytes: usize,
+    bytes: usize,
+}
+
+impl<'m, M: TextEstimator + ?Sized> StreamingEstimator<'m, M> {
+    pub fn new(method: &'m M) -> Self {
+        Self {
+            method,
+            window: String::new(),
+            window_full: false,
+            accumulator: method.text_accumulator(""),
+            decoder: Decoder::default(),
+            invalid_bytes: 0,
+            bytes: 0,
+        }
+    }
+
+    /// Handle invalid input in `push_bytes` with `policy`
+    pub fn with_invalid(mut self, policy: InvalidPolicy) -> Self {
+        self.decoder = Decoder::new(self.decoder.encoding(), policy);
+        self
+    }
+
+    /// Decode `push_bytes` input as `encoding` rather than UTF-8
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.decoder = Decoder::new(encoding, self.decoder.invalid());
+        self
+    }
+
+    /// Append the next chunk of text
+    pub fn push(&mut self, text: &str) {
+        self.bytes += text.len();
+        self.push_text(text);
+    }
+
+    fn push_text(&mut self, text: &str) {
+        if self.window_full {
+            self.accumulator.push(text);
+            return;
+        }
+
+        // Still filling the detection window: start over with the grown window,
+        // which costs at most `DETECTION_WINDOW` per push
+        let mut take = text.len().min(DETECTION_WINDOW - self.window.len());
+        while !text.is_char_boundary(take) {
+            take -= 1;
+        }
+        self.window.push_str(&text[..take]);
+        self.window_full = take < text.len();
+        self.accumulator = self.method.text_accumulator(&self.window);
+        self.accumulator.push(&self.window);
+        self.accumulator.push(&text[take..]);
+    }
+
+    /// Append raw bytes, decoded in place. A char split across calls is
+    /// completed by the next call; invalid bytes are handled by the policy set
+    /// with `with_invalid`, failing only under `InvalidPolicy::Error`
+    pub fn push_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let mut decoder = std::mem::take(&mut self.decoder);
+        let result = decoder.push(bytes, &mut |piece| self.accept(piece));
+        self.decoder = decoder;
+        result
+    }
+
+    fn accept(&mut self, piece: Decoded<'_>) {
+        match piece {
+            Decoded::Text(text) => self.push_text(text),
+            Decoded::Invalid(len) => self.invalid_bytes += len,
+        }
+    }
+
+    /// Push everything `reader` yields, through a fixed-size buffer, and return
+    /// the number of bytes read
+    pub fn read_from(&mut self, mut reader: impl Read) -> io::Result<usize> {
+        let mut buf = vec![0; READ_BUFFER];
+        let mut total = 0;
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) => return Ok(total),
+                Ok(n) => {
+                    self.push_bytes(&buf[..n])?;
+                    total += n;
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Estimate of everything pushed so far. An incomplete char still waiting
+    /// for its next bytes is not counted
+    pub fn estimate(&self) -> usize {
+        self.accumulator.estimate() + self.invalid_bytes
+    }
+
+    /// End the stream: a dangling incomplete char is invalid
+    pub fn finish(&mut self) -> io::Result<usize> {
+        let mut decoder = std::mem::take(&mut self.decoder);
+        let result = decoder.finish(&mut |piece| self.accept(piece));
+        self.decoder = decoder;
+        result.map(|()| self.estimate())
+    }
+
+    /// Bytes pushed so far, invalid ones and byte order marks included
+    pub fn len(&self) -> usize {
+        self.bytes + self.decoder.offset()
+    }
+
+    /// Invalid bytes counted as tokens under `InvalidPolicy::Bytes`
+    pub fn invalid_bytes(&self) -> usize {
+        self.invalid_bytes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for StreamingEstimator<'static> {
+    /// Streams through `DefaultMethod`
+    fn default() -> Self {
+        static METHOD: OnceLock<DefaultMethod> = OnceLock::new();
+        Self::new(METHOD.get_or_init(DefaultMethod::new))
+    }
+}
+
+/// Estimate text from `reader` with `method` in constant memory: bytes are
+/// streamed through the accumulators and the language is detected from the
+/// first `DETECTION_WINDOW` bytes. Invalid UTF-8 counts as U+FFFD
+pub fn estimate_reader_with<M: TextEstimator + ?Sized>(
+    method: &M,
+    reader: impl Read,
+) -> io::Result<usize> {
+    let mut stream = StreamingEstimator::new(method);
+    stream.read_from(reader)?;
+    stream.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::methods::method::EstimationMethod;
+    use crate::methods::method_basic::BasicMethod;
+
+    #[test]
+    fn test_matches_full_estimate() {
+        let text = "Streaming output arrives a few characters at a time. ".repeat(40);
+        let mut stream = StreamingEstimator::default();
+        for piece in text.as_bytes().chunks(7) {
+            stream.push(std::str::from_utf8(piece).unwrap());
+        }
+        assert_eq!(stream.len(), text.len());
+        assert_eq!(stream.estimate(), DefaultMethod::new().estimate(&text));
+
+        let method = BasicMethod::new();
+        let mut stream = StreamingEstimator::new(&method);
+        stream.push(&text);
+        assert_eq!(stream.estimate(), method.estimate(&text));
+    }
+
+    #[test]
+    #[cfg(feature = "multilingual")]
+    fn test_fence_after_detection_window() {
+        let prose = "The stream keeps going with plain English prose for a while. ".repeat(30);
+        let text = format!(
+            "{prose}\n\n```rust\nfn main() {{\n    println!(\"done\");\n}}\n```\n\n{prose}"
+        );
+        assert!(text.find("```").unwrap() > DETECTION_WINDOW);
+        let method = DefaultMethod::new();
+        let mut stream = StreamingEstimator::new(&method);
+        for piece in text.as_bytes().chunks(5) {
+            stream.push(std::str::from_utf8(piece).unwrap());
+        }
+        assert_eq!(stream.estimate(), method.estimate_text(&text));
+    }
+
+    #[test]
+    fn test_bytes_split_inside_chars() {
+        let text = "日本語のテキストをバイト単位で送ります。";
+        let mut stream = StreamingEstimator::default();
+        for byte in text.as_bytes() {
+            stream.push_bytes(&[*byte]).unwrap();
+        }
+        assert_eq!(
+            stream.finish().unwrap(),
+            DefaultMethod::new().estimate(text)
+        );
+    }
+
+    #[test]
+    fn test_invalid_policies() {
+        let input: &[&[u8]] = &[b"ok \xff", b" then \xe6", b"\x97"];
+        let push_all = |policy| {
+            let mut stream = StreamingEstimator::default().with_invalid(policy);
+            for bytes in input {
+                stream.push_bytes(bytes)?;
+            }
+            stream
+                .finish()
+                .map(|tokens| (tokens, stream.len(), stream.invalid_bytes()))
+        };
+
+        let method = DefaultMethod::new();
+        let (tokens, len, _) = push_all(InvalidPolicy::Lossy).unwrap();
+        assert_eq!(tokens, method.estimate("ok \u{fffd} then \u{fffd}"));
+        assert_eq!(len, 12);
+        let (tokens, _, invalid) = push_all(InvalidPolicy::Bytes).unwrap();
+        assert_eq!(invalid, 3);
+        assert_eq!(tokens, method.estimate("ok  then ") + 3);
+        let error = push_all(InvalidPolicy::Error).unwrap_err();
+        assert_eq!(error.to_string(), "invalid UTF-8 at byte 3");
+    }
+
+    #[test]
+    fn test_reader_across_buffers() {
+        // Multi-byte chars straddle the read buffer boundaries
+        let text = "ストリームから読み込んだテキスト。".repeat(5000);
+        assert!(text.len() > 3 * READ_BUFFER);
+        let method = DefaultMethod::new();
+        let tokens = estimate_reader_with(&method, io::Cursor::new(text.as_bytes())).unwrap();
+        assert_eq!(tokens, method.estimate(&text));
+    }
+}
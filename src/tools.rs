@@ -0,0 +1,283 @@
+use crate::params::ParameterBundle;
+use crate::{Method, DEFAULT_METHOD};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::error::Error;
+use std::fmt::Write as _;
+
+/// A function the model may call: name, description and JSON Schema parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionDefinition {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(
+        default,
+        alias = "input_schema",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub parameters: Option<Value>,
+}
+
+/// A tool definition, either OpenAI-style `{"type": "function", "function": {...}}`
+/// or a bare function definition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ToolDefinition {
+    Function { function: FunctionDefinition },
+    Bare(FunctionDefinition),
+}
+
+impl ToolDefinition {
+    pub fn function(&self) -> &FunctionDefinition {
+        match self {
+            ToolDefinition::Function { function } | ToolDefinition::Bare(function) => function,
+        }
+    }
+}
+
+/// Calibration for tool definitions on top of the rendered schema text
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolParameters {
+    /// Multiplier on the text estimate of the rendered definitions
+    pub text_coef: f32,
+    pub per_tool: f32,
+    pub per_property: f32,
+    pub per_enum_value: f32,
+    pub intercept: f32,
+}
+
+impl Default for ToolParameters {
+    fn default() -> Self {
+        // Generated from params/bundle.toml by build.rs
+        include!(concat!(env!("OUT_DIR"), "/params/tools.rs"))
+    }
+}
+
+/// Features of a set of tool definitions, used for estimation and fitting
+#[derive(Debug, Clone, Default)]
+pub struct ToolFeatures {
+    /// Text estimate of the definitions as rendered into the prompt
+    pub text_tokens: usize,
+    pub tool_count: usize,
+    pub property_count: usize,
+    pub enum_value_count: usize,
+}
+
+/// Parse a JSON array of tool definitions, or a request object with `tools`
+pub fn parse_tools(json: &str) -> Result<Vec<ToolDefinition>, Box<dyn Error>> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Tools {
+        Request { tools: Vec<ToolDefinition> },
+        List(Vec<ToolDefinition>),
+    }
+
+    Ok(match serde_json::from_str(json)? {
+        Tools::Request { tools } | Tools::List(tools) => tools,
+    })
+}
+
+/// Render tool definitions the way function-calling models see them: a
+/// TypeScript-like namespace of function signatures with comment descriptions
+pub fn render_tools(tools: &[ToolDefinition]) -> String {
+    let mut out = String::new();
+    if tools.is_empty() {
+        return out;
+    }
+
+    out.push_str("namespace functions {\n\n");
+    for tool in tools {
+        let function = tool.function();
+        if let Some(description) = &function.description {
+            render_comment(&mut out, description, "");
+        }
+        let _ = write!(out, "type {} = (", function.name);
+        let schema = function
+            .parameters
+            .as_ref()
+            .filter(|schema| properties(schema).is_some_and(|props| !props.is_empty()));
+        if let Some(schema) = schema {
+            out.push_str("_: {\n");
+            render_properties(&mut out, schema, "");
+            out.push('}');
+        }
+        out.push_str(") => any;\n\n");
+    }
+    out.push_str("} // namespace functions");
+    out
+}
+
+fn properties(schema: &Value) -> Option<&serde_json::Map<String, Value>> {
+    schema.get("properties").and_then(Value::as_object)
+}
+
+fn render_comment(out: &mut String, text: &str, indent: &str) {
+    for line in text.lines() {
+        let _ = writeln!(out, "{indent}// {line}");
+    }
+}
+
+fn render_properties(out: &mut String, schema: &Value, indent: &str) {
+    let Some(props) = properties(schema) else {
+        return;
+    };
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|r| r.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+
+    for (name, prop) in props {
+        if let Some(description) = prop.get("description").and_then(Value::as_str) {
+            render_comment(out, description, indent);
+        }
+        let optional = if required.contains(&name.as_str()) {
+            ""
+        } else {
+            "?"
+        };
+        let _ = write!(out, "{indent}{name}{optional}: ");
+        render_type(out, prop, indent);
+        out.push_str(",\n");
+    }
+}
+
+fn render_type(out: &mut String, schema: &Value, indent: &str) {
+    if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+        let values: Vec<String> = values.iter().map(Value::to_string).collect();
+        out.push_str(&values.join(" | "));
+        return;
+    }
+
+    match schema.get("type").and_then(Value::as_str) {
+        Some("string") => out.push_str("string"),
+        Some("number") | Some("integer") => out.push_str("number"),
+        Some("boolean") => out.push_str("boolean"),
+        Some("null") => out.push_str("null"),
+        Some("array") => {
+            match schema.get("items") {
+                Some(items) => render_type(out, items, indent),
+                None => out.push_str("any"),
+            }
+            out.push_str("[]");
+        }
+        Some("object") if properties(schema).is_some() => {
+            let inner = format!("{indent}  ");
+            out.push_str("{\n");
+            render_properties(out, schema, &inner);
+            let _ = write!(out, "{indent}}}");
+        }
+        Some("object") => out.push_str("object"),
+        _ => out.push_str("any"),
+    }
+}
+
+fn count_schema(schema: &Value, features: &mut ToolFeatures) {
+    if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+        features.enum_value_count += values.len();
+    }
+    if let Some(props) = properties(schema) {
+        features.property_count += props.len();
+        for prop in props.values() {
+            count_schema(prop, features);
+        }
+    }
+    if let Some(items) = schema.get("items") {
+        count_schema(items, features);
+    }
+}
+
+/// Estimates prompt tokens consumed by tool definitions
+pub struct ToolEstimator {
+    parameters: ToolParameters,
+    estimator: Box<dyn Fn(&str) -> usize + Send + Sync>,
+}
+
+impl ToolEstimator {
+    /// Estimator using the compiled-in parameters and the default text method
+    pub fn new() -> Self {
+        Self::from_bundle(&ParameterBundle::default(), DEFAULT_METHOD)
+    }
+
+    pub fn from_bundle(bundle: &ParameterBundle, method: Method) -> Self {
+        Self {
+            parameters: bundle.tools.clone(),
+            estimator: bundle.estimator(method),
+        }
+    }
+
+    pub fn count(&self, tools: &[ToolDefinition]) -> ToolFeatures {
+        let mut features = ToolFeatures {
+            text_tokens: (self.estimator)(&render_tools(tools)),
+            tool_count: tools.len(),
+            ..Default::default()
+        };
+        for tool in tools {
+            if let Some(schema) = &tool.function().parameters {
+                count_schema(schema, &mut features);
+            }
+        }
+        features
+    }
+
+    pub fn estimate(&self, tools: &[ToolDefinition]) -> usize {
+        if tools.is_empty() {
+            return 0;
+        }
+        let features = self.count(tools);
+        let params = &self.parameters;
+        let estimate = params.text_coef * features.text_tokens as f32
+            + params.per_tool * features.tool_count as f32
+            + params.per_property * features.property_count as f32
+            + params.per_enum_value * features.enum_value_count as f32
+            + params.intercept;
+
+        estimate.round().max(0.0) as usize
+    }
+}
+
+impl Default for ToolEstimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WEATHER: &str = r#"[{"type": "function", "function": {
+        "name": "get_weather",
+        "description": "Get the current weather",
+        "parameters": {
+            "type": "object",
+            "properties": {
+                "location": {"type": "string", "description": "City name"},
+                "unit": {"type": "string", "enum": ["celsius", "fahrenheit"]}
+            },
+            "required": ["location"]
+        }
+    }}]"#;
+
+    #[test]
+    fn test_render_tools() {
+        let tools = parse_tools(WEATHER).unwrap();
+        let rendered = render_tools(&tools);
+        assert!(rendered.contains("// Get the current weather\ntype get_weather = (_: {\n"));
+        assert!(rendered.contains("// City name\nlocation: string,\n"));
+        assert!(rendered.contains("unit?: \"celsius\" | \"fahrenheit\",\n"));
+    }
+
+    #[test]
+    fn test_count_tool_features() {
+        let tools = parse_tools(WEATHER).unwrap();
+        let estimator = ToolEstimator::new();
+        let features = estimator.count(&tools);
+        assert_eq!(features.tool_count, 1);
+        assert_eq!(features.property_count, 2);
+        assert_eq!(features.enum_value_count, 2);
+        assert!(estimator.estimate(&tools) > 0);
+        assert_eq!(estimator.estimate(&[]), 0);
+    }
+}
@@ -0,0 +1,443 @@
+use crate::methods::method::{Accumulator, TextEstimator};
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+
+/// Which part of the text `truncate_to_tokens` keeps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TruncateSide {
+    /// Keep the beginning
+    #[default]
+    Head,
+    /// Keep the end
+    Tail,
+    /// Keep the beginning and the end, joined by an ellipsis; just the
+    /// beginning when the budget leaves no room for the end
+    Middle,
+}
+
+impl TruncateSide {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TruncateSide::Head => "head",
+            TruncateSide::Tail => "tail",
+            TruncateSide::Middle => "middle",
+        }
+    }
+}
+
+impl fmt::Display for TruncateSide {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for TruncateSide {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [TruncateSide::Head, TruncateSide::Tail, TruncateSide::Middle]
+            .into_iter()
+            .find(|side| side.name() == s)
+            .ok_or_else(|| format!("unknown side: {s} (expected head, tail or middle)"))
+    }
+}
+
+/// Joins head and tail in `TruncateSide::Middle`
+pub const ELLIPSIS: &str = " … ";
+
+/// A cut at a sentence boundary is preferred if it keeps this share of the text
+const SENTENCE_SHARE: f64 = 0.8;
+
+/// Truncate `text` to at most `max_tokens` as estimated by `method`, cutting
+/// at word boundaries and preferably sentence boundaries. Text that already
+/// fits is returned unchanged
+pub fn truncate_with<'t, M: TextEstimator + ?Sized>(
+    method: &M,
+    text: &'t str,
+    max_tokens: usize,
+    side: TruncateSide,
+) -> Cow<'t, str> {
+    if fits_budget(method, text, max_tokens) {
+        return Cow::Borrowed(text);
+    }
+    match side {
+        TruncateSide::Head => Cow::Borrowed(&text[..head_end(method, text, max_tokens)]),
+        TruncateSide::Tail => Cow::Borrowed(&text[tail_start(method, text, max_tokens)..]),
+        TruncateSide::Middle => {
+            let joined = |head: &str, tail: &str| format!("{head}{ELLIPSIS}{tail}");
+            let fits =
+                |head: &str, tail: &str| fits_budget(method, &joined(head, tail), max_tokens);
+            let budget = max_tokens.saturating_sub(method.estimate_text(ELLIPSIS));
+            let head_with = |budget| &text[..head_end(method, text, budget)];
+            // Without room for a tail the ellipsis would only cost tokens
+            let head_cut = || Cow::Borrowed(head_with(max_tokens));
+            let mut head = head_with(budget / 2);
+            if !fits(head, "") {
+                // Joined with the ellipsis, the head can estimate higher than alone
+                match last_fitting(budget / 2, |budget| fits(head_with(budget), "")) {
+                    Some(budget) => head = head_with(budget),
+                    None => return head_cut(),
+                }
+            }
+
+            // One accumulator carries the head and the ellipsis into the tail,
+            // so the tail is sized for the joined text
+            let rest = &text[head.len()..];
+            let mut accumulator = method.text_accumulator(text);
+            accumulator.push(head);
+            accumulator.push(ELLIPSIS);
+            let start = tail_start_with(method, accumulator, rest, max_tokens, |start| {
+                fits(head, &rest[start..])
+            });
+            let tail = &rest[start..];
+            if tail.is_empty() {
+                return head_cut();
+            }
+            Cow::Owned(joined(head, tail))
+        }
+    }
+}
+
+/// Largest `n` in `0..=max` for which `fits(n)` holds, or `None` if not even 0
+/// fits. `max` is tried first, then the rest is binary searched, assuming the
+/// estimate grows with `n`. Multilingual accumulators detect the language of
+/// the whole text, which can differ from that of a prefix or suffix, so the
+/// accumulator's pick is checked with a full estimate
+pub(crate) fn last_fitting(max: usize, fits: impl Fn(usize) -> bool) -> Option<usize> {
+    if fits(max) {
+        return Some(max);
+    }
+    if max == 0 || !fits(0) {
+        return None;
+    }
+    let (mut lo, mut hi) = (0, max - 1);
+    while lo < hi {
+        let mid = hi - (hi - lo) / 2;
+        if fits(mid) {
+            lo = mid;
+        } else {
+            hi = mid - 1;
+        }
+    }
+    Some(lo)
+}
+
+/// Whether `text` is estimated within `budget`. An empty cut always fits, even
+/// for `BasicMethod`, whose intercept alone estimates empty text above 0
+pub(crate) fn fits_budget<M: TextEstimator + ?Sized>(
+    method: &M,
+    text: &str,
+    budget: usize,
+) -> bool {
+    text.is_empty() || method.estimate_text(text) <= budget
+}
+
+/// Start offsets of words: each word runs up to the next one, trailing
+/// whitespace included
+pub(crate) fn word_starts(text: &str) -> Vec<usize> {
+    let mut starts = vec![0];
+    let mut after_space = false;
+    for (i, c) in text.char_indices() {
+        if c.is_whitespace() {
+            after_space = true;
+        } else if after_space {
+            starts.push(i);
+            after_space = false;
+        }
+    }
+    starts
+}
+
+pub(crate) fn ends_sentence(word: &str) -> bool {
+    word.trim_end()
+        .trim_end_matches(['"', '\'', ')', ']', '”', '’'])
+        .ends_with(['.', '!', '?', '。', '！', '？'])
+}
+
+/// End of the longest prefix estimated within `budget`
+pub(crate) fn head_end<M: TextEstimator + ?Sized>(method: &M, text: &str, budget: usize) -> usize {
+    if budget == 0 {
+        return 0;
+    }
+    let starts = word_starts(text);
+    let end_of = |words: usize| starts.get(words).copied().unwrap_or(text.len());
+    let mut accumulator = method.text_accumulator(text);
+    let mut kept = 0;
+    let mut sentence_kept = 0;
+    for (i, &start) in starts.iter().enumerate() {
+        let word = &text[start..end_of(i + 1)];
+        accumulator.push(word);
+        if accumulator.estimate() > budget {
+            break;
+        }
+        kept = i + 1;
+        if ends_sentence(word) {
+            sentence_kept = kept;
+        }
+    }
+
+    let prefix_end = |words: usize| text[..end_of(words)].trim_end().len();
+    let fits = |words: usize| fits_budget(method, &text[..prefix_end(words)], budget);
+    if end_of(sentence_kept) as f64 >= end_of(kept) as f64 * SENTENCE_SHARE && fits(sentence_kept) {
+        return prefix_end(sentence_kept);
+    }
+    match last_fitting(kept, fits) {
+        Some(words) if words > 0 => prefix_end(words),
+        // The first word alone is over budget: cut inside it
+        _ => char_end(method, text, text, budget),
+    }
+}
+
+/// End of the longest prefix within `budget`, cut at any char. The
+/// accumulator is set up from `sample`
+pub(crate) fn char_end<M: TextEstimator + ?Sized>(
+    method: &M,
+    sample: &str,
+    text: &str,
+    budget: usize,
+) -> usize {
+    let mut accumulator = method.text_accumulator(sample);
+    let mut ends = vec![0];
+    for (i, c) in text.char_indices() {
+        accumulator.push(c.encode_utf8(&mut [0; 4]));
+        if accumulator.estimate() > budget {
+            break;
+        }
+        ends.push(i + c.len_utf8());
+    }
+    let fits = |chars: usize| fits_budget(method, &text[..ends[chars]], budget);
+    last_fitting(ends.len() - 1, fits).map_or(0, |chars| ends[chars])
+}
+
+/// Start of the longest suffix estimated within `budget`
+pub(crate) fn tail_start<M: TextEstimator + ?Sized>(
+    method: &M,
+    text: &str,
+    budget: usize,
+) -> usize {
+    let fits = |start: usize| fits_budget(method, &text[start..], budget);
+    tail_start_with(method, method.text_accumulator(text), text, budget, fits)
+}
+
+/// Start of the longest suffix for which `fits` holds, sized by pushing words
+/// to `accumulator` back to front until it is over `budget`
+fn tail_start_with<'m, M: TextEstimator + ?Sized>(
+    method: &'m M,
+    mut accumulator: Box<dyn Accumulator + 'm>,
+    text: &str,
+    budget: usize,
+    fits: impl Fn(usize) -> bool,
+) -> usize {
+    if budget == 0 {
+        return text.len();
+    }
+    let starts = word_starts(text);
+    let start_of = |words: usize| match words {
+        0 => text.len(),
+        _ => starts[starts.len() - words],
+    };
+
+    // Words are pushed back to front; the order only matters where a word
+    // straddles a line or a fence, which the check below corrects
+    let mut kept = 0;
+    let mut sentence_kept = None;
+    for (i, &start) in starts.iter().enumerate().rev() {
+        let word = &text[start..starts.get(i + 1).copied().unwrap_or(text.len())];
+        accumulator.push(word);
+        if accumulator.estimate() > budget {
+            break;
+        }
+        kept = starts.len() - i;
+        if i > 0 && ends_sentence(&text[starts[i - 1]..start]) {
+            sentence_kept = Some(kept);
+        }
+    }
+
+    let kept_len = |words: usize| (text.len() - start_of(words)) as f64;
+    let sentence =
+        sentence_kept.filter(|&words| kept_len(words) >= kept_len(kept) * SENTENCE_SHARE);
+    if let Some(words) = sentence.filter(|&words| fits(start_of(words))) {
+        return start_of(words);
+    }
+    match last_fitting(kept, |words| fits(start_of(words))) {
+        Some(words) if words > 0 => start_of(words),
+        // The last word alone is over budget: cut inside it
+        _ => char_start(method, text, budget, fits),
+    }
+}
+
+/// Start of the longest suffix for which `fits` holds, cut at any char
+fn char_start<M: TextEstimator + ?Sized>(
+    method: &M,
+    text: &str,
+    budget: usize,
+    fits: impl Fn(usize) -> bool,
+) -> usize {
+    let mut accumulator = method.text_accumulator(text);
+    let mut starts = vec![text.len()];
+    for (i, c) in text.char_indices().rev() {
+        accumulator.push(c.encode_utf8(&mut [0; 4]));
+        if accumulator.estimate() > budget {
+            break;
+        }
+        starts.push(i);
+    }
+    let start = last_fitting(starts.len() - 1, |chars| fits(starts[chars]))
+        .map_or(text.len(), |chars| starts[chars]);
+    let trimmed = text.len() - text[start..].trim_start().len();
+    if fits(trimmed) {
+        trimmed
+    } else {
+        start
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::methods::method::EstimationMethod;
+    use crate::{DefaultMethod, Method, ParameterBundle};
+
+    const TEXT: &str =
+        "The first sentence is short. The second one is a little longer than the first. \
+        A third follows it, and then a fourth closes the paragraph for good.";
+
+    #[test]
+    fn test_fitting_text_is_unchanged() {
+        let method = DefaultMethod::new();
+        for side in [TruncateSide::Head, TruncateSide::Tail, TruncateSide::Middle] {
+            assert_eq!(truncate_with(&method, "Short.", 100, side), "Short.");
+        }
+    }
+
+    #[test]
+    fn test_truncated_text_fits_the_budget() {
+        let method = DefaultMethod::new();
+        for budget in [1, 5, 12, 20] {
+            for side in [TruncateSide::Head, TruncateSide::Tail, TruncateSide::Middle] {
+                let truncated = truncate_with(&method, TEXT, budget, side);
+                assert!(method.estimate(&truncated) <= budget, "{side} {budget}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_middle_without_tail_is_a_head_cut() {
+        // The unbroken end leaves no room for a tail at small budgets
+        let text = format!("{TEXT} {}", "ab".repeat(200));
+        let bundle = ParameterBundle::default();
+        for &method in Method::ALL {
+            let estimator = bundle.text_estimator(method);
+            for budget in 1..=5 {
+                let truncated =
+                    truncate_with(estimator.as_ref(), &text, budget, TruncateSide::Middle);
+                assert!(!truncated.ends_with(ELLIPSIS), "{method} {budget}");
+                if !truncated.contains(ELLIPSIS) {
+                    let head = truncate_with(estimator.as_ref(), &text, budget, TruncateSide::Head);
+                    assert_eq!(truncated, head, "{method} {budget}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_small_budgets_for_every_method() {
+        let bundle = ParameterBundle::default();
+        for &method in Method::ALL {
+            let estimator = bundle.text_estimator(method);
+            for budget in 0..=5 {
+                for side in [TruncateSide::Head, TruncateSide::Tail, TruncateSide::Middle] {
+                    let truncated = truncate_with(estimator.as_ref(), TEXT, budget, side);
+                    assert!(
+                        fits_budget(estimator.as_ref(), &truncated, budget),
+                        "{method} {side} {budget}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_multilingual_and_fenced_code_fit() {
+        let text = format!(
+            "{TEXT}\n\n```rust\nfn main() {{\n    println!(\"{{}}\", 1 + 2);\n}}\n```\n\n\
+            日本語の文章も少し含めます。最後の文です。"
+        );
+        let bundle = ParameterBundle::default();
+        for &method in Method::ALL {
+            let estimator = bundle.text_estimator(method);
+            for budget in sample_budgets(estimator.estimate_text(&text)) {
+                for side in [TruncateSide::Head, TruncateSide::Tail, TruncateSide::Middle] {
+                    let truncated = truncate_with(estimator.as_ref(), &text, budget, side);
+                    assert!(
+                        fits_budget(estimator.as_ref(), &truncated, budget),
+                        "{method} {side} {budget}"
+                    );
+                }
+            }
+        }
+    }
+
+    /// Budgets below `total` to test: the small ones, the largest, and a stride
+    /// between them
+    fn sample_budgets(total: usize) -> Vec<usize> {
+        let mut budgets: Vec<usize> = (0..total.min(3)).collect();
+        budgets.extend((3..total).step_by((total / 4).max(1)));
+        budgets.push(total.saturating_sub(1));
+        budgets.dedup();
+        budgets
+    }
+
+    /// Counts full estimates, to check cuts are not found by stepping
+    struct Counting<'m> {
+        method: &'m dyn TextEstimator,
+        calls: std::cell::Cell<usize>,
+    }
+
+    impl TextEstimator for Counting<'_> {
+        fn estimate_text(&self, text: &str) -> usize {
+            self.calls.set(self.calls.get() + 1);
+            self.method.estimate_text(text)
+        }
+
+        fn text_accumulator(&self, sample: &str) -> Box<dyn Accumulator + '_> {
+            self.method.text_accumulator(sample)
+        }
+    }
+
+    #[test]
+    fn test_full_estimates_are_logarithmic() {
+        let text = format!("{TEXT} 日本語の文章も少し含めます。").repeat(200);
+        let bundle = ParameterBundle::default();
+        for &method in Method::ALL {
+            let estimator = bundle.text_estimator(method);
+            for side in [TruncateSide::Head, TruncateSide::Tail, TruncateSide::Middle] {
+                let counting = Counting {
+                    method: estimator.as_ref(),
+                    calls: std::cell::Cell::new(0),
+                };
+                truncate_with(&counting, &text, 2000, side);
+                assert!(counting.calls.get() <= 40, "{method} {side}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_cuts_at_boundaries() {
+        let method = DefaultMethod::new();
+        // Room for the first sentence but not the second
+        let budget = method.estimate("The first sentence is short.") + 1;
+        let head = truncate_with(&method, TEXT, budget, TruncateSide::Head);
+        assert!(TEXT.starts_with(&*head));
+        assert!(head.ends_with('.'), "{head}");
+        let budget = method
+            .estimate("A third follows it, and then a fourth closes the paragraph for good.")
+            + 1;
+        let tail = truncate_with(&method, TEXT, budget, TruncateSide::Tail);
+        assert!(TEXT.ends_with(&*tail));
+        assert!(tail.starts_with(char::is_uppercase), "{tail}");
+        let middle = truncate_with(&method, TEXT, budget, TruncateSide::Middle);
+        assert!(middle.starts_with("The first") && middle.contains(ELLIPSIS));
+    }
+}
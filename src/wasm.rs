@@ -0,0 +1,197 @@
+use crate::{chunk_with, ChunkOptions, ParameterBundle, TextEstimator, DEFAULT_METHOD};
+use serde::Deserialize;
+use std::sync::OnceLock;
+use wasm_bindgen::prelude::*;
+
+/// There is no filesystem to load params from, so the bundle is compiled in,
+/// profiles included
+const BUNDLE_TOML: &str = include_str!("../params/bundle.toml");
+
+// The methods a build accepts depend on the multilingual feature. The custom
+// section's use of these is invisible to the dead code lint
+#[cfg(feature = "multilingual")]
+#[allow(dead_code)]
+const METHOD_TS: &str = r#"
+export type Method = "simple" | "basic" | "multilingual" | "multilingual_simple" | "json" | "code";
+"#;
+
+#[cfg(not(feature = "multilingual"))]
+#[allow(dead_code)]
+const METHOD_TS: &str = r#"
+export type Method = "simple" | "basic" | "json" | "code";
+"#;
+
+#[wasm_bindgen(typescript_custom_section)]
+const METHOD_SECTION: &str = METHOD_TS;
+
+#[wasm_bindgen(typescript_custom_section)]
+const OPTIONS_TS: &str = r#"
+export interface EstimateOptions {
+    /** Defaults to "multilingual_simple", or "simple" in builds without the multilingual feature */
+    method?: Method;
+    /** Named profile from params/bundle.toml */
+    profile?: string;
+}
+
+export interface ChunkOptions extends EstimateOptions {
+    /** Tokens repeated at the start of the next chunk */
+    overlap?: number;
+    /** Share of maxTokens held back for estimation error */
+    margin?: number;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "EstimateOptions")]
+    pub type JsEstimateOptions;
+
+    #[wasm_bindgen(typescript_type = "ChunkOptions")]
+    pub type JsChunkOptions;
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Options {
+    method: Option<String>,
+    profile: Option<String>,
+    overlap: usize,
+    margin: f64,
+}
+
+impl Options {
+    fn from_js(value: Option<JsValue>) -> Result<Self, JsError> {
+        match value {
+            Some(value) if !value.is_undefined() && !value.is_null() => {
+                Ok(serde_wasm_bindgen::from_value(value)?)
+            }
+            _ => Ok(Self::default()),
+        }
+    }
+
+    fn estimator(&self) -> Result<Box<dyn TextEstimator + Send + Sync>, JsError> {
+        let method = match &self.method {
+            Some(name) => name.parse().map_err(|e: String| JsError::new(&e))?,
+            None => DEFAULT_METHOD,
+        };
+        let bundle = bundle()?;
+        match &self.profile {
+            Some(name) => bundle
+                .profile(name)
+                .map(|profile| profile.text_estimator(method))
+                .ok_or_else(|| JsError::new(&format!("unknown profile: {name}"))),
+            None => Ok(bundle.text_estimator(method)),
+        }
+    }
+}
+
+fn bundle() -> Result<&'static ParameterBundle, JsError> {
+    static BUNDLE: OnceLock<Result<ParameterBundle, String>> = OnceLock::new();
+    BUNDLE
+        .get_or_init(|| ParameterBundle::from_toml_str(BUNDLE_TOML).map_err(|e| e.to_string()))
+        .as_ref()
+        .map_err(|e| JsError::new(e))
+}
+
+/// Estimate the tokens of `text`
+#[wasm_bindgen(js_name = estimateTokens)]
+pub fn estimate_tokens(text: &str, options: Option<JsEstimateOptions>) -> Result<usize, JsError> {
+    let options = Options::from_js(options.map(Into::into))?;
+    Ok(options.estimator()?.estimate_text(text))
+}
+
+/// Estimate each of `texts`, building the estimator once
+#[wasm_bindgen(js_name = estimateTokensBatch)]
+pub fn estimate_tokens_batch(
+    texts: Vec<String>,
+    options: Option<JsEstimateOptions>,
+) -> Result<Vec<usize>, JsError> {
+    let estimator = Options::from_js(options.map(Into::into))?.estimator()?;
+    Ok(texts
+        .iter()
+        .map(|text| estimator.estimate_text(text))
+        .collect())
+}
+
+/// Split `text` into chunks estimated under `maxTokens`, cutting at paragraph,
+/// sentence or word boundaries
+#[wasm_bindgen(js_name = chunk)]
+pub fn chunk(
+    text: &str,
+    #[wasm_bindgen(js_name = maxTokens)] max_tokens: usize,
+    options: Option<JsChunkOptions>,
+) -> Result<Vec<String>, JsError> {
+    let options = Options::from_js(options.map(Into::into))?;
+    let chunk_options = ChunkOptions {
+        overlap: options.overlap,
+        margin: options.margin,
+        ..ChunkOptions::new(max_tokens)
+    };
+    let estimator = options.estimator()?;
+    Ok(chunk_with(estimator.as_ref(), text, &chunk_options)
+        .into_iter()
+        .map(str::to_string)
+        .collect())
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use super::*;
+    use crate::Method;
+    use serde::Serialize;
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    fn options<T: JsCast>(value: serde_json::Value) -> Option<T> {
+        let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+        Some(value.serialize(&serializer).unwrap().unchecked_into())
+    }
+
+    #[wasm_bindgen_test]
+    fn test_estimate_matches_native() {
+        let text = "Hello, world! こんにちは、世界。";
+        let bundle = ParameterBundle::default();
+        assert_eq!(
+            estimate_tokens(text, None).unwrap(),
+            bundle.text_estimator(DEFAULT_METHOD).estimate_text(text)
+        );
+        let basic = estimate_tokens(text, options(serde_json::json!({"method": "basic"})));
+        assert_eq!(
+            basic.unwrap(),
+            bundle.text_estimator(Method::Basic).estimate_text(text)
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn test_batch_and_chunk() {
+        let texts = vec!["one".to_string(), "two words".to_string()];
+        let batch = estimate_tokens_batch(texts.clone(), None).unwrap();
+        let single: Vec<usize> = texts
+            .iter()
+            .map(|text| estimate_tokens(text, None).unwrap())
+            .collect();
+        assert_eq!(batch, single);
+
+        let text = "First sentence here. Second sentence here. ".repeat(20);
+        let chunks = chunk(&text, 20, options(serde_json::json!({"overlap": 2}))).unwrap();
+        assert!(chunks.len() > 1);
+        assert!(chunks
+            .iter()
+            .all(|c| estimate_tokens(c, None).unwrap() <= 20));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_unknown_method_and_profile() {
+        assert!(estimate_tokens("text", options(serde_json::json!({"method": "bpe"}))).is_err());
+        assert!(estimate_tokens("text", options(serde_json::json!({"profile": "nope"}))).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn test_method_type_lists_built_methods() {
+        let names: Vec<String> = Method::ALL
+            .iter()
+            .map(|method| format!("\"{}\"", method.name()))
+            .collect();
+        assert!(METHOD_TS.contains(&names.join(" | ")));
+    }
+}
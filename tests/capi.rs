@@ -0,0 +1,85 @@
+#![cfg(feature = "capi")]
+
+use skimtoken::{Method, ParameterBundle};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+// Must match the text in tests/capi/test_capi.c
+const TEXT: &str = "Hello from C, こんにちは";
+
+#[test]
+fn test_c_harness() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let tmp = Path::new(env!("CARGO_TARGET_TMPDIR"));
+
+    // The library is only an rlib by default, so build the shared library
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let status = Command::new(cargo)
+        .args([
+            "rustc",
+            "--lib",
+            "--features",
+            "capi",
+            "--crate-type",
+            "cdylib",
+        ])
+        .arg("--manifest-path")
+        .arg(root.join("Cargo.toml"))
+        .arg("--target-dir")
+        .arg(tmp.join("capi"))
+        .status()
+        .expect("failed to run cargo");
+    assert!(status.success());
+    let lib_dir = tmp.join("capi/debug");
+    let binary = tmp.join("test_capi");
+
+    let compiler = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let status = Command::new(compiler)
+        .arg(root.join("tests/capi/test_capi.c"))
+        .arg("-I")
+        .arg(root.join("include"))
+        .arg("-L")
+        .arg(&lib_dir)
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-lskimtoken")
+        .arg("-o")
+        .arg(&binary)
+        .status()
+        .expect("failed to run the C compiler");
+    assert!(status.success());
+
+    // cargo puts target/debug on LD_LIBRARY_PATH, which would win over the
+    // rpath if another build left a libskimtoken.so there
+    let output = Command::new(&binary)
+        .env("LD_LIBRARY_PATH", &lib_dir)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let expected = ParameterBundle::default()
+        .text_estimator(Method::Basic)
+        .estimate_text(TEXT);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        expected.to_string()
+    );
+}
+
+#[test]
+fn test_checked_in_header_is_current() {
+    let generated = include_str!(concat!(env!("OUT_DIR"), "/skimtoken.h"));
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("include/skimtoken.h");
+    // SKIMTOKEN_UPDATE_HEADER=1 cargo test --features capi --test capi
+    if std::env::var_os("SKIMTOKEN_UPDATE_HEADER").is_some() {
+        fs::write(&path, generated).unwrap();
+        return;
+    }
+    assert!(
+        fs::read_to_string(&path).unwrap() == generated,
+        "include/skimtoken.h is stale; rerun with SKIMTOKEN_UPDATE_HEADER=1"
+    );
+}
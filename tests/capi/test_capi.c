@@ -0,0 +1,41 @@
+/* Exercises the C ABI; run by tests/capi.rs, which checks the printed estimate */
+#include <stdio.h>
+#include <string.h>
+
+#include "skimtoken.h"
+
+#define CHECK(cond)                                                  \
+    do {                                                             \
+        if (!(cond)) {                                               \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__,   \
+                    __LINE__, #cond);                                \
+            return 1;                                                \
+        }                                                            \
+    } while (0)
+
+int main(void) {
+    const char *text = "Hello from C, \xe3\x81\x93\xe3\x82\x93\xe3\x81\xab\xe3\x81\xa1\xe3\x81\xaf";
+
+    SkimtokenEstimator *estimator = skimtoken_estimator_new("basic", NULL);
+    CHECK(estimator != NULL);
+    size_t tokens = skimtoken_estimate(estimator, (const uint8_t *)text, strlen(text));
+    CHECK(tokens != SKIMTOKEN_ERROR);
+    CHECK(skimtoken_last_error() == NULL);
+    CHECK(skimtoken_estimate(estimator, NULL, 0) == 0);
+    skimtoken_estimator_free(estimator);
+
+    /* Custom parameters: everything not given keeps its default */
+    SkimtokenEstimator *custom = skimtoken_estimator_new("simple", "[simple]\ncoefficient = 1.0\n");
+    CHECK(custom != NULL);
+    CHECK(skimtoken_estimate(custom, (const uint8_t *)"abcd", 4) == 4);
+    skimtoken_estimator_free(custom);
+
+    CHECK(skimtoken_estimator_new("bpe", NULL) == NULL);
+    CHECK(strcmp(skimtoken_last_error(), "unknown method: bpe") == 0);
+    CHECK(skimtoken_estimate(NULL, (const uint8_t *)text, 1) == SKIMTOKEN_ERROR);
+    CHECK(strcmp(skimtoken_last_error(), "handle is NULL") == 0);
+    skimtoken_estimator_free(NULL);
+
+    printf("%zu\n", tokens);
+    return 0;
+}
@@ -0,0 +1,120 @@
+#![cfg(feature = "cli")]
+
+use std::process::Command;
+
+const DOC: &str = "Setup takes a minute. Install the crate and call it from main.\n\n\
+```rust\nfn main() {\n    let tokens = skimtoken::estimate_tokens(\"Hello, world!\");\n    \
+println!(\"{tokens}\");\n}\n```\n\nThe estimate is printed to stdout.\n";
+
+fn run(args: &[&str]) -> usize {
+    let output = Command::new(env!("CARGO_BIN_EXE_skimtoken"))
+        .args(args)
+        .output()
+        .expect("failed to run skimtoken");
+    assert!(output.status.success(), "{args:?}");
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .trim()
+        .parse()
+        .unwrap()
+}
+
+#[test]
+fn test_fenced_input_matches_library() {
+    let expected = skimtoken::estimate_tokens(DOC);
+    assert_eq!(run(&[DOC]), expected);
+
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("fenced.md");
+    std::fs::write(&path, DOC).unwrap();
+    let path = path.to_str().unwrap();
+    assert_eq!(run(&["-f", path]), expected);
+    assert_eq!(run(&["--sample", "4", "-f", path]), expected);
+}
+
+#[test]
+fn test_mixed_language_file_matches_library() {
+    // The language changes after the first KiB, which streaming would not see
+    let doc = format!(
+        "{}{}",
+        "An English introduction that runs on for a while. ".repeat(30),
+        "その後の本文は日本語で書かれていて、かなり長く続きます。".repeat(400)
+    );
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("mixed.txt");
+    std::fs::write(&path, &doc).unwrap();
+    assert_eq!(
+        run(&["-f", path.to_str().unwrap()]),
+        skimtoken::estimate_tokens(&doc)
+    );
+}
+
+#[test]
+fn test_invalid_bytes_in_subcommands() {
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("invalid.json");
+    std::fs::write(
+        &path,
+        b"{\"messages\": [{\"role\": \"user\", \"content\": \"hi \xFF\xFE\"}]}",
+    )
+    .unwrap();
+    let path = path.to_str().unwrap();
+    let skipped = run(&["--invalid", "skip", "chat", "-f", path]);
+    assert_eq!(
+        run(&["--invalid", "bytes", "chat", "-f", path]),
+        skipped + 2
+    );
+
+    // A breakdown has no single estimate to add the bytes to
+    let output = Command::new(env!("CARGO_BIN_EXE_skimtoken"))
+        .args(["--invalid", "bytes", "markdown", "-f", path])
+        .output()
+        .expect("failed to run skimtoken");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_file_takes_precedence_over_text() {
+    // UTF-16 cannot be seeked into, so --sample reads it in memory
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("utf16.txt");
+    let mut bytes = vec![0xFF, 0xFE];
+    bytes.extend(DOC.encode_utf16().flat_map(u16::to_le_bytes));
+    std::fs::write(&path, bytes).unwrap();
+    let path = path.to_str().unwrap();
+    let expected = run(&["-f", path, "ignored"]);
+    assert_eq!(expected, skimtoken::estimate_tokens(DOC));
+    assert_eq!(run(&["--sample", "4", "-f", path, "ignored"]), expected);
+}
+
+#[test]
+#[cfg(feature = "multilingual")]
+fn test_fit_drops_sparse_languages_from_bundle() {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR"));
+    let mut dataset = String::new();
+    for n in 1..=12 {
+        let text = "The quick brown fox jumps over the lazy dog. ".repeat(n);
+        dataset += &serde_json::json!({"text": text, "token_len": 10 * n}).to_string();
+        dataset.push('\n');
+    }
+    for n in 1..=3 {
+        let text = "吾輩は猫である。名前はまだ無い。".repeat(n);
+        dataset += &serde_json::json!({"text": text, "token_len": 12 * n}).to_string();
+        dataset.push('\n');
+    }
+    let dataset_path = dir.join("sparse.jsonl");
+    std::fs::write(&dataset_path, dataset).unwrap();
+    let output_path = dir.join("sparse_bundle.toml");
+    std::fs::copy("params/bundle.toml", &output_path).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_skimtoken"))
+        .args(["fit", "-m", "multilingual", "-d"])
+        .arg(&dataset_path)
+        .arg("-o")
+        .arg(&output_path)
+        .output()
+        .expect("failed to run skimtoken");
+    assert!(output.status.success());
+
+    let bundle = skimtoken::ParameterBundle::load(&output_path).unwrap();
+    let languages: Vec<&String> = bundle.multilingual.language_params.keys().collect();
+    assert_eq!(languages, ["eng"]);
+    assert_eq!(bundle.multilingual.language_samples["eng"], 12);
+    assert!(!bundle.multilingual.language_samples.contains_key("jpn"));
+}
@@ -0,0 +1,87 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+// Release builds with LTO take minutes, so these only run on request:
+// cargo test --test size -- --ignored --nocapture
+
+/// Build with `args` and `features` only, returning the size of `artifact`
+/// under the release directory
+fn release_size(args: &[&str], features: &str, artifact: &str) -> u64 {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("size");
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let status = Command::new(cargo)
+        .args(args)
+        .args(["--release", "--no-default-features", "--features", features])
+        .arg("--manifest-path")
+        .arg(root.join("Cargo.toml"))
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .status()
+        .expect("failed to run cargo");
+    assert!(status.success(), "build with {features} failed");
+    let path = target_dir.join(artifact);
+    fs::metadata(&path)
+        .unwrap_or_else(|e| panic!("{}: {e}", path.display()))
+        .len()
+}
+
+fn report(kind: &str, sizes: &[(&str, u64)]) {
+    for (features, size) in sizes {
+        println!(
+            "{kind:<6} {features:<20} {:>8.1} KiB",
+            *size as f64 / 1024.0
+        );
+    }
+}
+
+#[test]
+#[ignore]
+fn test_cli_size_per_features() {
+    let build = |features| {
+        release_size(
+            &["build", "--bin", "skimtoken"],
+            features,
+            "release/skimtoken",
+        )
+    };
+    let sizes = [
+        ("cli", build("cli")),
+        ("cli,multilingual", build("cli,multilingual")),
+    ];
+    report("cli", &sizes);
+    assert!(
+        sizes[0].1 < sizes[1].1,
+        "language detection should add size"
+    );
+}
+
+/// Needs `rustup target add wasm32-unknown-unknown`
+#[test]
+#[ignore]
+fn test_wasm_size_per_features() {
+    let build = |features| {
+        release_size(
+            &[
+                "rustc",
+                "--lib",
+                "--crate-type",
+                "cdylib",
+                "--target",
+                "wasm32-unknown-unknown",
+            ],
+            features,
+            "wasm32-unknown-unknown/release/skimtoken.wasm",
+        )
+    };
+    let sizes = [
+        ("wasm", build("wasm")),
+        ("wasm,multilingual", build("wasm,multilingual")),
+    ];
+    report("wasm", &sizes);
+    assert!(
+        sizes[0].1 < sizes[1].1,
+        "language detection should add size"
+    );
+}
@@ -2,7 +2,24 @@
 Comprehensive tests for skimtoken covering normal and edge cases.
 """
 
+import contextlib
+import io
+import json
+import os
+import tempfile
+
 from skimtoken import (
+    main,
+    estimate_chat_tokens,
+    estimate_tool_tokens,
+    estimate_markdown_tokens,
+    markdown_breakdown,
+    estimate_html_tokens,
+    strip_html,
+    truncate_to_tokens,
+    chunk,
+    estimate_tokens_sampled,
+    StreamingEstimator,
     estimate_tokens,
     estimate_tokens_basic,
     estimate_tokens_simple,
@@ -131,6 +148,19 @@ class TestMultilingualCases:
         assert multi >= 5
         assert multi_simple >= 5
 
+    def test_language_hint(self):
+        """A language tag skips detection; its forms are equivalent."""
+        text = "こんにちは、世界！これはテストです。"
+        for estimate in (estimate_tokens_multilingual, estimate_tokens_multilingual_simple):
+            hinted = {estimate(text, language=tag) for tag in ("ja", "jpn", "ja-JP")}
+            assert hinted == {estimate(text)}
+            assert estimate(text, language="en") != estimate(text)
+            try:
+                estimate(text, language="klingon")
+                assert False, "expected ValueError"
+            except ValueError:
+                pass
+
 
 class TestEdgeCases:
     """Test edge cases and boundary conditions."""
@@ -321,3 +351,215 @@ class TestSpecialFormats:
         text = "name,age,city\nJohn,30,NYC\nJane,25,LA"
         result = estimate_tokens(text)
         assert result > 10
+
+
+class TestChat:
+    """Test chat conversation estimation."""
+
+    def test_messages_include_framing(self):
+        """Framing overhead is added on top of the message contents."""
+        messages = [
+            {"role": "system", "content": "You are a helpful assistant."},
+            {"role": "user", "content": "What is the capital of France?"},
+        ]
+        content = sum(estimate_tokens(m["content"]) for m in messages)
+        assert estimate_chat_tokens(messages) > content
+
+    def test_request_and_json_forms(self):
+        """Request dicts and JSON strings give the same estimate as message lists."""
+        messages = [{"role": "user", "name": "alice", "content": "Hi there"}]
+        expected = estimate_chat_tokens(messages)
+        assert estimate_chat_tokens({"model": "gpt-4o", "messages": messages}) == expected
+        raw = '[{"role": "user", "name": "alice", "content": "Hi there"}]'
+        assert estimate_chat_tokens(raw) == expected
+
+    def test_tools_are_counted(self):
+        """Tool definitions add to the request estimate."""
+        tools = [
+            {
+                "type": "function",
+                "function": {
+                    "name": "get_weather",
+                    "description": "Get the current weather",
+                    "parameters": {
+                        "type": "object",
+                        "properties": {"location": {"type": "string"}},
+                        "required": ["location"],
+                    },
+                },
+            }
+        ]
+        messages = [{"role": "user", "content": "Weather in Paris?"}]
+        assert estimate_tool_tokens(tools) > 0
+        assert estimate_tool_tokens([]) == 0
+        with_tools = estimate_chat_tokens({"messages": messages, "tools": tools})
+        assert with_tools == estimate_chat_tokens(messages) + estimate_tool_tokens(tools)
+
+
+class TestMarkdown:
+    """Test Markdown estimation."""
+
+    def test_breakdown_blocks(self):
+        """Blocks are reported in order with their section and kind."""
+        doc = "# Title\n\nSome prose.\n\n```python\nprint('hi')\n```\n"
+        blocks = markdown_breakdown(doc)
+        assert [b["kind"] for b in blocks] == ["heading", "paragraph", "code"]
+        assert all(b["section"] == "Title" for b in blocks)
+        assert blocks[2]["start_line"] == 5
+        assert sum(b["tokens"] for b in blocks) == estimate_markdown_tokens(doc)
+
+
+class TestHtml:
+    """Test HTML estimation."""
+
+    def test_markup_and_stripped(self):
+        """Raw estimate splits into markup and text; stripping drops the markup."""
+        page = '<html><body><p class="intro">Hello <b>world</b></p><script>var x;</script></body></html>'
+        raw, markup, text, stripped = estimate_html_tokens(page)
+        assert raw == markup + text
+        assert stripped < raw
+        assert strip_html(page) == "Hello world"
+
+
+class TestTruncate:
+    """Test truncation to a token budget."""
+
+    def test_sides(self):
+        """Each side keeps its part of the text and fits the budget."""
+        text = "First sentence here. " * 20
+        assert truncate_to_tokens("Short.", 100) == "Short."
+        head = truncate_to_tokens(text, 10)
+        assert text.startswith(head) and estimate_tokens(head) <= 10
+        tail = truncate_to_tokens(text, 10, side="tail")
+        assert text.endswith(tail) and estimate_tokens(tail) <= 10
+        assert " … " in truncate_to_tokens(text, 10, side="middle")
+
+
+class TestChunk:
+    """Test chunking under a token budget."""
+
+    def test_chunks_fit(self):
+        """Every chunk fits the budget and the chunks cover the text."""
+        text = "\n\n".join(f"Paragraph {i} has a few words in it." for i in range(30))
+        chunks = chunk(text, 40)
+        assert len(chunks) > 1
+        assert all(estimate_tokens(c) <= 40 for c in chunks)
+        assert " ".join(chunks).split() == text.split()
+        assert all(c.startswith("Paragraph") for c in chunks)
+
+    def test_overlap(self):
+        """Consecutive chunks share text when overlap is set."""
+        text = " ".join(f"word{i}" for i in range(200))
+        chunks = chunk(text, 30, overlap=5)
+        assert chunks[0].split()[-1] in chunks[1]
+
+
+class TestStreaming:
+    """Test the streaming estimator."""
+
+    def test_matches_full_estimate(self):
+        """Pushing text piece by piece gives the full-text estimate."""
+        text = "Tokens stream in one small piece after another. " * 30
+        stream = StreamingEstimator()
+        for i in range(0, len(text), 5):
+            stream.push(text[i : i + 5])
+        assert stream.estimate() == estimate_tokens(text)
+
+    def test_bytes_split_inside_chars(self):
+        """UTF-8 sequences split across push_bytes calls are reassembled."""
+        text = "こんにちは、世界。ストリーミングのテストです。"
+        stream = StreamingEstimator()
+        for b in text.encode("utf-8"):
+            stream.push_bytes(bytes([b]))
+        assert stream.finish() == estimate_tokens(text)
+        assert len(stream) == len(text.encode("utf-8"))
+
+    def test_invalid_policies(self):
+        """Invalid bytes are replaced, counted per byte, or rejected."""
+        stream = StreamingEstimator(invalid="bytes")
+        stream.push_bytes(b"abc \xff\xfe")
+        assert stream.finish() == estimate_tokens("abc ") + 2
+        stream = StreamingEstimator(invalid="error")
+        try:
+            stream.push_bytes(b"abc \xff")
+            assert False, "expected ValueError"
+        except ValueError:
+            pass
+        stream = StreamingEstimator(invalid="skip")
+        stream.push_bytes(b"abc \xff")
+        assert stream.finish() == estimate_tokens("abc ")
+
+    def test_encodings(self):
+        """UTF-16 and Latin-1 bytes decode to the same estimate as the text."""
+        text = "Grüße aus 日本"
+        for encoding, data in [
+            ("utf-16le", text.encode("utf-16-le")),
+            ("utf-16be", text.encode("utf-16-be")),
+            ("utf-8", text.encode("utf-16")),  # the BOM wins
+        ]:
+            stream = StreamingEstimator(encoding=encoding)
+            stream.push_bytes(data)
+            assert stream.finish() == estimate_tokens(text)
+        stream = StreamingEstimator(encoding="latin-1")
+        stream.push_bytes("Grüße".encode("latin-1"))
+        assert stream.finish() == estimate_tokens("Grüße")
+
+
+class TestSampled:
+    """Test sampled estimation."""
+
+    def test_close_to_full_estimate(self):
+        """Sampling a large text stays close to the full estimate and reports an error."""
+        text = "Sampling spreads windows across the whole text. " * 20000
+        tokens, error = estimate_tokens_sampled(text)
+        full = estimate_tokens(text)
+        assert abs(tokens - full) / full < 0.05
+        assert error >= 0
+        assert estimate_tokens_sampled("short text") == (estimate_tokens("short text"), 0.0)
+
+    def test_rejects_empty_windows(self):
+        """A window of 0 bytes would sample nothing."""
+        try:
+            estimate_tokens_sampled("text", window_bytes=0)
+            assert False, "expected ValueError"
+        except ValueError:
+            pass
+
+
+class TestCli:
+    """Test the console script subcommands."""
+
+    def run(self, argv: list[str]) -> str:
+        out = io.StringIO()
+        with contextlib.redirect_stdout(out), contextlib.redirect_stderr(io.StringIO()):
+            main(argv)
+        return out.getvalue()
+
+    def test_chat(self):
+        """chat prints the same estimate as estimate_chat_tokens."""
+        request = {"messages": [{"role": "user", "content": "Hello there"}]}
+        with tempfile.TemporaryDirectory() as tmp:
+            path = os.path.join(tmp, "request.json")
+            with open(path, "w", encoding="utf-8") as f:
+                json.dump(request, f)
+            assert self.run(["chat", "-f", path]) == f"{estimate_chat_tokens(request)}\n"
+
+    def test_split(self):
+        """split writes the chunks of chunk() to numbered files."""
+        text = "First sentence here. Second sentence here. " * 20
+        with tempfile.TemporaryDirectory() as tmp:
+            path = os.path.join(tmp, "doc.txt")
+            with open(path, "w", encoding="utf-8") as f:
+                f.write(text)
+            out = os.path.join(tmp, "chunks")
+            lines = self.run(["split", "-f", path, "-o", out, "--max-tokens", "20"]).splitlines()
+            chunks = chunk(text, 20)
+            assert len(lines) == len(chunks) > 1
+            with open(os.path.join(out, "chunk-0001.txt"), encoding="utf-8") as f:
+                assert f.read() == chunks[0]
+            try:
+                self.run(["split", "-f", path, "-o", out])
+                assert False, "expected SystemExit"
+            except SystemExit:
+                pass
+